@@ -1,5 +1,8 @@
 use crate::{
-    backend::{error::ContextError, native_gpu_future},
+    backend::{
+        error::{ContextError, CreationSites, TrackedId},
+        native_gpu_future,
+    },
     AdapterInfo, BindGroupDescriptor, BindGroupLayoutDescriptor, BindingResource, BufferBinding,
     CommandEncoderDescriptor, ComputePassDescriptor, ComputePipelineDescriptor,
     DownlevelProperties, Features, Label, Limits, LoadOp, MapMode, Operations,
@@ -24,7 +27,14 @@ use std::{
 
 const LABEL: &str = "label";
 
-pub struct Context(wgc::hub::Global<wgc::hub::IdentityManagerFactory>);
+type BufferMapCallback = Box<dyn FnOnce(Result<(), crate::BufferAsyncError>) + Send>;
+
+pub struct Context(
+    wgc::hub::Global<wgc::hub::IdentityManagerFactory>,
+    CreationSites,
+    /// Error sinks of the live devices, for the errors that aren't raised by a device call.
+    Mutex<Vec<(wgc::id::DeviceId, ErrorSink)>>,
+);
 
 impl Drop for Context {
     fn drop(&mut self) {
@@ -62,6 +72,23 @@ impl Context {
         }
     }
 
+    /// Records where a resource was created, to be shown in error messages.
+    #[cfg(feature = "track-caller")]
+    pub(crate) fn record_creation_site(
+        &self,
+        id: &impl TrackedId,
+        location: &'static std::panic::Location<'static>,
+    ) {
+        self.1.record(id, location);
+    }
+
+    pub(super) fn creation_site(
+        &self,
+        id: &impl TrackedId,
+    ) -> Option<&'static std::panic::Location<'static>> {
+        self.1.get(id)
+    }
+
     fn handle_error(
         &self,
         sink_mutex: &Mutex<ErrorSinkRaw>,
@@ -70,30 +97,54 @@ impl Context {
         label: Label,
         string: &'static str,
     ) {
-        let error = ContextError {
+        let error = self.make_error(cause, label_key, label, string);
+        self.report_error(sink_mutex, error);
+    }
+
+    /// Wraps `cause` into the error reported to the user.
+    fn make_error(
+        &self,
+        cause: impl Error + Send + Sync + 'static,
+        label_key: &'static str,
+        label: Label,
+        string: &'static str,
+    ) -> crate::Error {
+        let mut error = ContextError {
             string,
             cause: Box::new(cause),
             label: label.unwrap_or_default().to_string(),
             label_key,
+            report: crate::ErrorReport::default(),
         };
-        let sink = sink_mutex.lock();
-        let mut source_opt: Option<&(dyn Error + 'static)> = Some(&error);
-        while let Some(source) = source_opt {
-            if let Some(wgc::device::DeviceError::OutOfMemory) =
-                source.downcast_ref::<wgc::device::DeviceError>()
-            {
-                return sink.handle_error(crate::Error::OutOfMemoryError {
-                    source: Box::new(error),
-                });
-            }
-            source_opt = source.source();
+        let (description, report) = self.format_error(&error);
+        error.report = report;
+        if let Some(wgc::device::DeviceError::OutOfMemory) = find_device_error(&error) {
+            return crate::Error::OutOfMemoryError {
+                source: Box::new(error),
+            };
         }
 
         // Otherwise, it is a validation error
-        sink.handle_error(crate::Error::ValidationError {
-            description: self.format_error(&error),
+        crate::Error::ValidationError {
+            description,
             source: Box::new(error),
-        });
+        }
+    }
+
+    /// Passes `error` to the matching error scope, or to the uncaptured error handler.
+    ///
+    /// Errors caused by the loss of the device are signaled as such instead.
+    fn report_error(&self, sink_mutex: &Mutex<ErrorSinkRaw>, error: crate::Error) {
+        let mut sink = sink_mutex.lock();
+        if !sink.check_device_lost(&error) {
+            sink.handle_error(error);
+        }
+    }
+
+    /// Signals the loss of `device` if `error`, which is returned to the user
+    /// instead of being reported, was caused by it.
+    pub(crate) fn device_check_lost(&self, device: &Device, error: &crate::Error) {
+        device.error_sink.lock().check_device_lost(error);
     }
 
     fn handle_error_nolabel(
@@ -112,6 +163,437 @@ impl Context {
     ) -> ! {
         panic!("Error in {}: {}", string, cause);
     }
+
+    /// Same as `device_create_shader_module`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_shader_module_with_error(
+        &self,
+        device: &Device,
+        desc: &ShaderModuleDescriptor,
+    ) -> (wgc::id::ShaderModuleId, Option<crate::Error>) {
+        let global = &self.0;
+        let descriptor = wgc::pipeline::ShaderModuleDescriptor {
+            label: desc.label.map(Borrowed),
+            flags: desc.flags,
+        };
+        let source = match desc.source {
+            ShaderSource::SpirV(ref spv) => wgc::pipeline::ShaderModuleSource::SpirV(Borrowed(spv)),
+            ShaderSource::Wgsl(ref code) => wgc::pipeline::ShaderModuleSource::Wgsl(Borrowed(code)),
+        };
+        let (id, error) = wgc::gfx_select!(
+            device.id => global.device_create_shader_module(device.id, &descriptor, source, PhantomData)
+        );
+        let error = error
+            .map(|cause| self.make_error(cause, LABEL, desc.label, "Device::create_shader_module"));
+        (id, error)
+    }
+
+    /// Same as `device_create_bind_group_layout`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_bind_group_layout_with_error(
+        &self,
+        device: &Device,
+        desc: &BindGroupLayoutDescriptor,
+    ) -> (wgc::id::BindGroupLayoutId, Option<crate::Error>) {
+        let global = &self.0;
+        let descriptor = wgc::binding_model::BindGroupLayoutDescriptor {
+            label: desc.label.map(Borrowed),
+            entries: Borrowed(desc.entries),
+        };
+        let (id, error) = wgc::gfx_select!(
+            device.id => global.device_create_bind_group_layout(device.id, &descriptor, PhantomData)
+        );
+        let error = error.map(|cause| {
+            self.make_error(cause, LABEL, desc.label, "Device::create_bind_group_layout")
+        });
+        (id, error)
+    }
+
+    /// Same as `device_create_bind_group`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_bind_group_with_error(
+        &self,
+        device: &Device,
+        desc: &BindGroupDescriptor,
+    ) -> (wgc::id::BindGroupId, Option<crate::Error>) {
+        use wgc::binding_model as bm;
+
+        let mut arrayed_texture_views = Vec::new();
+        if device
+            .features
+            .contains(Features::SAMPLED_TEXTURE_BINDING_ARRAY)
+        {
+            // gather all the array view IDs first
+            for entry in desc.entries.iter() {
+                if let BindingResource::TextureViewArray(array) = entry.resource {
+                    arrayed_texture_views.extend(array.iter().map(|view| view.id));
+                }
+            }
+        }
+        let mut remaining_arrayed_texture_views = &arrayed_texture_views[..];
+
+        let mut arrayed_buffer_bindings = Vec::new();
+        if device.features.contains(Features::BUFFER_BINDING_ARRAY) {
+            // gather all the buffers first
+            for entry in desc.entries.iter() {
+                if let BindingResource::BufferArray(array) = entry.resource {
+                    arrayed_buffer_bindings.extend(array.iter().map(|binding| bm::BufferBinding {
+                        buffer_id: binding.buffer.id.id,
+                        offset: binding.offset,
+                        size: binding.size,
+                    }));
+                }
+            }
+        }
+        let mut remaining_arrayed_buffer_bindings = &arrayed_buffer_bindings[..];
+
+        let entries = desc
+            .entries
+            .iter()
+            .map(|entry| bm::BindGroupEntry {
+                binding: entry.binding,
+                resource: match entry.resource {
+                    BindingResource::Buffer(BufferBinding {
+                        buffer,
+                        offset,
+                        size,
+                    }) => bm::BindingResource::Buffer(bm::BufferBinding {
+                        buffer_id: buffer.id.id,
+                        offset,
+                        size,
+                    }),
+                    BindingResource::BufferArray(array) => {
+                        let slice = &remaining_arrayed_buffer_bindings[..array.len()];
+                        remaining_arrayed_buffer_bindings =
+                            &remaining_arrayed_buffer_bindings[array.len()..];
+                        bm::BindingResource::BufferArray(Borrowed(slice))
+                    }
+                    BindingResource::Sampler(sampler) => bm::BindingResource::Sampler(sampler.id),
+                    BindingResource::TextureView(texture_view) => {
+                        bm::BindingResource::TextureView(texture_view.id)
+                    }
+                    BindingResource::TextureViewArray(array) => {
+                        let slice = &remaining_arrayed_texture_views[..array.len()];
+                        remaining_arrayed_texture_views =
+                            &remaining_arrayed_texture_views[array.len()..];
+                        bm::BindingResource::TextureViewArray(Borrowed(slice))
+                    }
+                },
+            })
+            .collect::<Vec<_>>();
+        let descriptor = bm::BindGroupDescriptor {
+            label: desc.label.as_ref().map(|label| Borrowed(&label[..])),
+            layout: desc.layout.id,
+            entries: Borrowed(&entries),
+        };
+
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_bind_group(
+            device.id,
+            &descriptor,
+            PhantomData
+        ));
+        let error = error
+            .map(|cause| self.make_error(cause, LABEL, desc.label, "Device::create_bind_group"));
+        (id, error)
+    }
+
+    /// Same as `device_create_pipeline_layout`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_pipeline_layout_with_error(
+        &self,
+        device: &Device,
+        desc: &PipelineLayoutDescriptor,
+    ) -> (wgc::id::PipelineLayoutId, Option<crate::Error>) {
+        // Limit is always less or equal to wgc::MAX_BIND_GROUPS, so this is always right
+        // Guards following ArrayVec
+        assert!(
+            desc.bind_group_layouts.len() <= wgc::MAX_BIND_GROUPS,
+            "Bind group layout count {} exceeds device bind group limit {}",
+            desc.bind_group_layouts.len(),
+            wgc::MAX_BIND_GROUPS
+        );
+
+        let temp_layouts = desc
+            .bind_group_layouts
+            .iter()
+            .map(|bgl| bgl.id)
+            .collect::<ArrayVec<[_; wgc::MAX_BIND_GROUPS]>>();
+        let descriptor = wgc::binding_model::PipelineLayoutDescriptor {
+            label: desc.label.map(Borrowed),
+            bind_group_layouts: Borrowed(&temp_layouts),
+            push_constant_ranges: Borrowed(&desc.push_constant_ranges),
+        };
+
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_pipeline_layout(
+            device.id,
+            &descriptor,
+            PhantomData
+        ));
+        let error = error.map(|cause| {
+            self.make_error(cause, LABEL, desc.label, "Device::create_pipeline_layout")
+        });
+        (id, error)
+    }
+
+    /// Same as `device_create_render_pipeline`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_render_pipeline_with_error(
+        &self,
+        device: &Device,
+        desc: &RenderPipelineDescriptor,
+    ) -> (wgc::id::RenderPipelineId, Option<crate::Error>) {
+        use wgc::pipeline as pipe;
+
+        let vertex_buffers: ArrayVec<[_; wgc::device::MAX_VERTEX_BUFFERS]> = desc
+            .vertex
+            .buffers
+            .iter()
+            .map(|vbuf| pipe::VertexBufferLayout {
+                array_stride: vbuf.array_stride,
+                step_mode: vbuf.step_mode,
+                attributes: Borrowed(vbuf.attributes),
+            })
+            .collect();
+
+        let implicit_pipeline_ids = match desc.layout {
+            Some(_) => None,
+            None => Some(wgc::device::ImplicitPipelineIds {
+                root_id: PhantomData,
+                group_ids: &[PhantomData; wgc::MAX_BIND_GROUPS],
+            }),
+        };
+        let descriptor = pipe::RenderPipelineDescriptor {
+            label: desc.label.map(Borrowed),
+            layout: desc.layout.map(|l| l.id),
+            vertex: pipe::VertexState {
+                stage: pipe::ProgrammableStageDescriptor {
+                    module: desc.vertex.module.id,
+                    entry_point: Borrowed(desc.vertex.entry_point),
+                },
+                buffers: Borrowed(&vertex_buffers),
+            },
+            primitive: desc.primitive,
+            depth_stencil: desc.depth_stencil.clone(),
+            multisample: desc.multisample,
+            fragment: desc.fragment.as_ref().map(|frag| pipe::FragmentState {
+                stage: pipe::ProgrammableStageDescriptor {
+                    module: frag.module.id,
+                    entry_point: Borrowed(frag.entry_point),
+                },
+                targets: Borrowed(frag.targets),
+            }),
+        };
+
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_render_pipeline(
+            device.id,
+            &descriptor,
+            PhantomData,
+            implicit_pipeline_ids
+        ));
+        let error = error.map(|cause| {
+            if let wgc::pipeline::CreateRenderPipelineError::Internal { stage, ref error } = cause {
+                log::warn!("Shader translation error for stage {:?}: {}", stage, error);
+                log::warn!("Please report it to https://github.com/gfx-rs/naga");
+                log::warn!("Try enabling `wgpu/cross` feature as a workaround.");
+            }
+            self.make_error(cause, LABEL, desc.label, "Device::create_render_pipeline")
+        });
+        (id, error)
+    }
+
+    /// Same as `device_create_compute_pipeline`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_compute_pipeline_with_error(
+        &self,
+        device: &Device,
+        desc: &ComputePipelineDescriptor,
+    ) -> (wgc::id::ComputePipelineId, Option<crate::Error>) {
+        use wgc::pipeline as pipe;
+
+        let implicit_pipeline_ids = match desc.layout {
+            Some(_) => None,
+            None => Some(wgc::device::ImplicitPipelineIds {
+                root_id: PhantomData,
+                group_ids: &[PhantomData; wgc::MAX_BIND_GROUPS],
+            }),
+        };
+        let descriptor = pipe::ComputePipelineDescriptor {
+            label: desc.label.map(Borrowed),
+            layout: desc.layout.map(|l| l.id),
+            stage: pipe::ProgrammableStageDescriptor {
+                module: desc.module.id,
+                entry_point: Borrowed(desc.entry_point),
+            },
+        };
+
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_compute_pipeline(
+            device.id,
+            &descriptor,
+            PhantomData,
+            implicit_pipeline_ids
+        ));
+        let error = error.map(|cause| {
+            if let wgc::pipeline::CreateComputePipelineError::Internal(ref error) = cause {
+                log::warn!(
+                    "Shader translation error for stage {:?}: {}",
+                    wgt::ShaderStage::COMPUTE,
+                    error
+                );
+                log::warn!("Please report it to https://github.com/gfx-rs/naga");
+                log::warn!("Try enabling `wgpu/cross` feature as a workaround.");
+            }
+            self.make_error(cause, LABEL, desc.label, "Device::create_compute_pipeline")
+        });
+        (id, error)
+    }
+
+    /// Same as `device_create_buffer`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_buffer_with_error(
+        &self,
+        device: &Device,
+        desc: &crate::BufferDescriptor<'_>,
+    ) -> (Buffer, Option<crate::Error>) {
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_buffer(
+            device.id,
+            &desc.map_label(|l| l.map(Borrowed)),
+            PhantomData
+        ));
+        let error =
+            error.map(|cause| self.make_error(cause, LABEL, desc.label, "Device::create_buffer"));
+        (
+            Buffer {
+                id,
+                error_sink: Arc::clone(&device.error_sink),
+            },
+            error,
+        )
+    }
+
+    /// Same as `device_create_texture`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_texture_with_error(
+        &self,
+        device: &Device,
+        desc: &TextureDescriptor,
+    ) -> (Texture, Option<crate::Error>) {
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_texture(
+            device.id,
+            &desc.map_label(|l| l.map(Borrowed)),
+            PhantomData
+        ));
+        let error =
+            error.map(|cause| self.make_error(cause, LABEL, desc.label, "Device::create_texture"));
+        (
+            Texture {
+                id,
+                error_sink: Arc::clone(&device.error_sink),
+            },
+            error,
+        )
+    }
+
+    /// Same as `device_create_sampler`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_sampler_with_error(
+        &self,
+        device: &Device,
+        desc: &SamplerDescriptor,
+    ) -> (wgc::id::SamplerId, Option<crate::Error>) {
+        let descriptor = wgc::resource::SamplerDescriptor {
+            label: desc.label.map(Borrowed),
+            address_modes: [
+                desc.address_mode_u,
+                desc.address_mode_v,
+                desc.address_mode_w,
+            ],
+            mag_filter: desc.mag_filter,
+            min_filter: desc.min_filter,
+            mipmap_filter: desc.mipmap_filter,
+            lod_min_clamp: desc.lod_min_clamp,
+            lod_max_clamp: desc.lod_max_clamp,
+            compare: desc.compare,
+            anisotropy_clamp: desc.anisotropy_clamp,
+            border_color: desc.border_color,
+        };
+
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_sampler(
+            device.id,
+            &descriptor,
+            PhantomData
+        ));
+        let error =
+            error.map(|cause| self.make_error(cause, LABEL, desc.label, "Device::create_sampler"));
+        (id, error)
+    }
+
+    /// Same as `device_create_query_set`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_query_set_with_error(
+        &self,
+        device: &Device,
+        desc: &wgt::QuerySetDescriptor,
+    ) -> (wgc::id::QuerySetId, Option<crate::Error>) {
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_query_set(
+            device.id,
+            &desc,
+            PhantomData
+        ));
+        let error = error.map(|cause| self.make_error(cause, "", None, "Device::create_query_set"));
+        (id, error)
+    }
+
+    /// Same as `device_create_command_encoder`, but returns the error raised along
+    /// with the id, instead of reporting it to the error sink.
+    pub(crate) fn device_create_command_encoder_with_error(
+        &self,
+        device: &Device,
+        desc: &CommandEncoderDescriptor,
+    ) -> (CommandEncoder, Option<crate::Error>) {
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_command_encoder(
+            device.id,
+            &desc.map_label(|l| l.map(Borrowed)),
+            PhantomData
+        ));
+        let error = error.map(|cause| {
+            self.make_error(cause, LABEL, desc.label, "Device::create_command_encoder")
+        });
+        (
+            CommandEncoder {
+                id,
+                error_sink: Arc::clone(&device.error_sink),
+                open: true,
+            },
+            error,
+        )
+    }
+}
+
+fn find_device_error(error: &(dyn Error + 'static)) -> Option<&wgc::device::DeviceError> {
+    let mut source_opt = Some(error);
+    while let Some(source) = source_opt {
+        if let Some(device_error) = source.downcast_ref::<wgc::device::DeviceError>() {
+            return Some(device_error);
+        }
+        source_opt = source.source();
+    }
+    None
+}
+
+fn is_device_lost(error: &(dyn Error + 'static)) -> bool {
+    matches!(
+        find_device_error(error),
+        Some(wgc::device::DeviceError::Lost)
+    )
 }
 
 mod pass_impl {
@@ -391,11 +873,16 @@ mod pass_impl {
             wgpu_render_pass_end_pipeline_statistics_query(self)
         }
 
-        fn execute_bundles<'a, I: Iterator<Item = &'a wgc::id::RenderBundleId>>(
+        fn execute_bundles<'a, I: Iterator<Item = &'a Option<wgc::id::RenderBundleId>>>(
             &mut self,
             render_bundles: I,
         ) {
-            let temp_render_bundles = render_bundles.cloned().collect::<SmallVec<[_; 4]>>();
+            // Bundles whose encoder couldn't be created are skipped, their error
+            // has already been reported.
+            let temp_render_bundles = render_bundles
+                .flatten()
+                .cloned()
+                .collect::<SmallVec<[_; 4]>>();
             unsafe {
                 wgpu_render_pass_execute_bundles(
                     self,
@@ -433,7 +920,127 @@ mod pass_impl {
             offset: wgt::BufferAddress,
             size: Option<wgt::BufferSize>,
         ) {
-            self.set_index_buffer(buffer.id, index_format, offset, size)
+            self.set_index_buffer(buffer.id, index_format, offset, size)
+        }
+        fn set_vertex_buffer(
+            &mut self,
+            slot: u32,
+            buffer: &super::Buffer,
+            offset: wgt::BufferAddress,
+            size: Option<wgt::BufferSize>,
+        ) {
+            wgpu_render_bundle_set_vertex_buffer(self, slot, buffer.id, offset, size)
+        }
+
+        fn set_push_constants(&mut self, stages: wgt::ShaderStage, offset: u32, data: &[u8]) {
+            unsafe {
+                wgpu_render_bundle_set_push_constants(
+                    self,
+                    stages,
+                    offset,
+                    data.len().try_into().unwrap(),
+                    data.as_ptr(),
+                )
+            }
+        }
+        fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) {
+            wgpu_render_bundle_draw(
+                self,
+                vertices.end - vertices.start,
+                instances.end - instances.start,
+                vertices.start,
+                instances.start,
+            )
+        }
+        fn draw_indexed(&mut self, indices: Range<u32>, base_vertex: i32, instances: Range<u32>) {
+            wgpu_render_bundle_draw_indexed(
+                self,
+                indices.end - indices.start,
+                instances.end - instances.start,
+                indices.start,
+                base_vertex,
+                instances.start,
+            )
+        }
+        fn draw_indirect(
+            &mut self,
+            indirect_buffer: &super::Buffer,
+            indirect_offset: wgt::BufferAddress,
+        ) {
+            wgpu_render_bundle_draw_indirect(self, indirect_buffer.id, indirect_offset)
+        }
+        fn draw_indexed_indirect(
+            &mut self,
+            indirect_buffer: &super::Buffer,
+            indirect_offset: wgt::BufferAddress,
+        ) {
+            wgpu_render_bundle_draw_indexed_indirect(self, indirect_buffer.id, indirect_offset)
+        }
+        fn multi_draw_indirect(
+            &mut self,
+            _indirect_buffer: &super::Buffer,
+            _indirect_offset: wgt::BufferAddress,
+            _count: u32,
+        ) {
+            unimplemented!()
+        }
+        fn multi_draw_indexed_indirect(
+            &mut self,
+            _indirect_buffer: &super::Buffer,
+            _indirect_offset: wgt::BufferAddress,
+            _count: u32,
+        ) {
+            unimplemented!()
+        }
+        fn multi_draw_indirect_count(
+            &mut self,
+            _indirect_buffer: &super::Buffer,
+            _indirect_offset: wgt::BufferAddress,
+            _count_buffer: &super::Buffer,
+            _count_buffer_offset: wgt::BufferAddress,
+            _max_count: u32,
+        ) {
+            unimplemented!()
+        }
+        fn multi_draw_indexed_indirect_count(
+            &mut self,
+            _indirect_buffer: &super::Buffer,
+            _indirect_offset: wgt::BufferAddress,
+            _count_buffer: &super::Buffer,
+            _count_buffer_offset: wgt::BufferAddress,
+            _max_count: u32,
+        ) {
+            unimplemented!()
+        }
+    }
+
+    // An encoder whose creation failed records nothing.
+    impl crate::RenderInner<Context> for super::RenderBundleEncoder {
+        fn set_pipeline(&mut self, pipeline: &wgc::id::RenderPipelineId) {
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::set_pipeline(encoder, pipeline)
+            }
+        }
+        fn set_bind_group(
+            &mut self,
+            index: u32,
+            bind_group: &wgc::id::BindGroupId,
+            offsets: &[wgt::DynamicOffset],
+        ) {
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::set_bind_group(encoder, index, bind_group, offsets)
+            }
+        }
+        fn set_index_buffer(
+            &mut self,
+            buffer: &super::Buffer,
+            index_format: wgt::IndexFormat,
+            offset: wgt::BufferAddress,
+            size: Option<wgt::BufferSize>,
+        ) {
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::set_index_buffer(encoder, buffer, index_format, offset, size)
+            }
         }
         fn set_vertex_buffer(
             &mut self,
@@ -442,88 +1049,110 @@ mod pass_impl {
             offset: wgt::BufferAddress,
             size: Option<wgt::BufferSize>,
         ) {
-            wgpu_render_bundle_set_vertex_buffer(self, slot, buffer.id, offset, size)
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::set_vertex_buffer(encoder, slot, buffer, offset, size)
+            }
         }
-
         fn set_push_constants(&mut self, stages: wgt::ShaderStage, offset: u32, data: &[u8]) {
-            unsafe {
-                wgpu_render_bundle_set_push_constants(
-                    self,
-                    stages,
-                    offset,
-                    data.len().try_into().unwrap(),
-                    data.as_ptr(),
-                )
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::set_push_constants(encoder, stages, offset, data)
             }
         }
         fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) {
-            wgpu_render_bundle_draw(
-                self,
-                vertices.end - vertices.start,
-                instances.end - instances.start,
-                vertices.start,
-                instances.start,
-            )
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::draw(encoder, vertices, instances)
+            }
         }
         fn draw_indexed(&mut self, indices: Range<u32>, base_vertex: i32, instances: Range<u32>) {
-            wgpu_render_bundle_draw_indexed(
-                self,
-                indices.end - indices.start,
-                instances.end - instances.start,
-                indices.start,
-                base_vertex,
-                instances.start,
-            )
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::draw_indexed(encoder, indices, base_vertex, instances)
+            }
         }
         fn draw_indirect(
             &mut self,
             indirect_buffer: &super::Buffer,
             indirect_offset: wgt::BufferAddress,
         ) {
-            wgpu_render_bundle_draw_indirect(self, indirect_buffer.id, indirect_offset)
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::draw_indirect(encoder, indirect_buffer, indirect_offset)
+            }
         }
         fn draw_indexed_indirect(
             &mut self,
             indirect_buffer: &super::Buffer,
             indirect_offset: wgt::BufferAddress,
         ) {
-            wgpu_render_bundle_draw_indexed_indirect(self, indirect_buffer.id, indirect_offset)
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::draw_indexed_indirect(encoder, indirect_buffer, indirect_offset)
+            }
         }
         fn multi_draw_indirect(
             &mut self,
-            _indirect_buffer: &super::Buffer,
-            _indirect_offset: wgt::BufferAddress,
-            _count: u32,
+            indirect_buffer: &super::Buffer,
+            indirect_offset: wgt::BufferAddress,
+            count: u32,
         ) {
-            unimplemented!()
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::multi_draw_indirect(
+                    encoder,
+                    indirect_buffer,
+                    indirect_offset,
+                    count,
+                )
+            }
         }
         fn multi_draw_indexed_indirect(
             &mut self,
-            _indirect_buffer: &super::Buffer,
-            _indirect_offset: wgt::BufferAddress,
-            _count: u32,
+            indirect_buffer: &super::Buffer,
+            indirect_offset: wgt::BufferAddress,
+            count: u32,
         ) {
-            unimplemented!()
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::multi_draw_indexed_indirect(
+                    encoder,
+                    indirect_buffer,
+                    indirect_offset,
+                    count,
+                )
+            }
         }
         fn multi_draw_indirect_count(
             &mut self,
-            _indirect_buffer: &super::Buffer,
-            _indirect_offset: wgt::BufferAddress,
-            _count_buffer: &super::Buffer,
-            _count_buffer_offset: wgt::BufferAddress,
-            _max_count: u32,
+            indirect_buffer: &super::Buffer,
+            indirect_offset: wgt::BufferAddress,
+            count_buffer: &super::Buffer,
+            count_buffer_offset: wgt::BufferAddress,
+            max_count: u32,
         ) {
-            unimplemented!()
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::multi_draw_indirect_count(
+                    encoder,
+                    indirect_buffer,
+                    indirect_offset,
+                    count_buffer,
+                    count_buffer_offset,
+                    max_count,
+                )
+            }
         }
         fn multi_draw_indexed_indirect_count(
             &mut self,
-            _indirect_buffer: &super::Buffer,
-            _indirect_offset: wgt::BufferAddress,
-            _count_buffer: &super::Buffer,
-            _count_buffer_offset: wgt::BufferAddress,
-            _max_count: u32,
+            indirect_buffer: &super::Buffer,
+            indirect_offset: wgt::BufferAddress,
+            count_buffer: &super::Buffer,
+            count_buffer_offset: wgt::BufferAddress,
+            max_count: u32,
         ) {
-            unimplemented!()
+            if let Some(encoder) = &mut self.encoder {
+                crate::RenderInner::multi_draw_indexed_indirect_count(
+                    encoder,
+                    indirect_buffer,
+                    indirect_offset,
+                    count_buffer,
+                    count_buffer_offset,
+                    max_count,
+                )
+            }
         }
     }
 }
@@ -601,6 +1230,39 @@ pub(crate) struct Texture {
     error_sink: ErrorSink,
 }
 
+impl TrackedId for Buffer {
+    fn resource_type(&self) -> crate::ResourceType {
+        self.id.resource_type()
+    }
+
+    fn resource_id(&self) -> crate::ResourceId {
+        self.id.resource_id()
+    }
+}
+
+impl TrackedId for Texture {
+    fn resource_type(&self) -> crate::ResourceType {
+        self.id.resource_type()
+    }
+
+    fn resource_id(&self) -> crate::ResourceId {
+        self.id.resource_id()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RenderBundleEncoder {
+    /// `None` if the creation of the encoder failed.
+    encoder: Option<wgc::command::RenderBundleEncoder>,
+    error_sink: ErrorSink,
+}
+
+#[derive(Debug)]
+pub(crate) struct SwapChain {
+    id: wgc::id::SwapChainId,
+    error_sink: ErrorSink,
+}
+
 #[derive(Debug)]
 pub(crate) struct CommandEncoder {
     id: wgc::id::CommandEncoderId,
@@ -627,10 +1289,10 @@ impl crate::Context for Context {
     type ComputePassId = wgc::command::ComputePass;
     type RenderPassId = wgc::command::RenderPass;
     type CommandBufferId = wgc::id::CommandBufferId;
-    type RenderBundleEncoderId = wgc::command::RenderBundleEncoder;
-    type RenderBundleId = wgc::id::RenderBundleId;
+    type RenderBundleEncoderId = RenderBundleEncoder;
+    type RenderBundleId = Option<wgc::id::RenderBundleId>;
     type SurfaceId = wgc::id::SurfaceId;
-    type SwapChainId = wgc::id::SwapChainId;
+    type SwapChainId = SwapChain;
 
     type SwapChainOutputDetail = SwapChainOutputDetail;
 
@@ -638,14 +1300,15 @@ impl crate::Context for Context {
     #[allow(clippy::type_complexity)]
     type RequestDeviceFuture =
         Ready<Result<(Self::DeviceId, Self::QueueId), crate::RequestDeviceError>>;
-    type MapAsyncFuture = native_gpu_future::GpuFuture<Result<(), crate::BufferAsyncError>>;
+    type PopErrorScopeFuture = Ready<Result<Option<crate::Error>, crate::PopErrorScopeError>>;
+    type DeviceLostFuture = native_gpu_future::GpuFuture<crate::DeviceLostInfo>;
 
     fn init(backends: wgt::BackendBit) -> Self {
-        Self(wgc::hub::Global::new(
-            "wgpu",
-            wgc::hub::IdentityManagerFactory,
-            backends,
-        ))
+        Self(
+            wgc::hub::Global::new("wgpu", wgc::hub::IdentityManagerFactory, backends),
+            CreationSites::default(),
+            Mutex::new(Vec::new()),
+        )
     }
 
     fn instance_create_surface(
@@ -671,9 +1334,17 @@ impl crate::Context for Context {
 
     fn instance_poll_all_devices(&self, force_wait: bool) {
         let global = &self.0;
-        match global.poll_all_devices(force_wait) {
-            Ok(()) => (),
-            Err(err) => self.handle_error_fatal(err, "Device::poll"),
+        if global.poll_all_devices(force_wait).is_ok() {
+            return;
+        }
+        // The error doesn't tell which device raised it, so poll the devices
+        // one by one to report it to the error sink of the right one.
+        let devices = self.2.lock().clone();
+        for (id, error_sink) in devices {
+            match wgc::gfx_select!(id => global.device_poll(id, force_wait)) {
+                Ok(()) => (),
+                Err(cause) => self.handle_error_nolabel(&error_sink, cause, "Instance::poll_all"),
+            }
         }
     }
 
@@ -691,13 +1362,17 @@ impl crate::Context for Context {
             PhantomData
         ));
         if let Some(err) = error {
-            self.handle_error_fatal(err, "Adapter::request_device");
+            log::error!("Error in Adapter::request_device: {}", err);
+            return ready(Err(crate::RequestDeviceError::new(err)));
         }
         let device = Device {
             id: device_id,
             error_sink: Arc::new(Mutex::new(ErrorSinkRaw::new())),
             features: desc.features,
         };
+        self.2
+            .lock()
+            .push((device_id, Arc::clone(&device.error_sink)));
         ready(Ok((device, device_id)))
     }
 
@@ -705,13 +1380,15 @@ impl crate::Context for Context {
         &self,
         adapter: &Self::AdapterId,
         surface: &Self::SurfaceId,
-    ) -> Option<TextureFormat> {
+    ) -> Result<TextureFormat, crate::SwapChainPreferredFormatError> {
         let global = &self.0;
         match wgc::gfx_select!(adapter => global.adapter_get_swap_chain_preferred_format(*adapter, *surface))
         {
-            Ok(swap_chain_preferred_format) => Some(swap_chain_preferred_format),
-            Err(wgc::instance::GetSwapChainPreferredFormatError::UnsupportedQueueFamily) => None,
-            Err(err) => self.handle_error_fatal(err, "Adapter::get_swap_chain_preferred_format"),
+            Ok(swap_chain_preferred_format) => Ok(swap_chain_preferred_format),
+            Err(wgc::instance::GetSwapChainPreferredFormatError::UnsupportedQueueFamily) => {
+                Err(crate::SwapChainPreferredFormatError::Incompatible)
+            }
+            Err(err) => Err(crate::SwapChainPreferredFormatError::Backend(Arc::new(err))),
         }
     }
 
@@ -792,9 +1469,12 @@ impl crate::Context for Context {
     ) -> Self::SwapChainId {
         let global = &self.0;
         let (sc, error) = wgc::gfx_select!(device.id => global.device_create_swap_chain(device.id, *surface, desc));
-        match error {
-            Some(e) => self.handle_error_fatal(e, "Device::create_swap_chain"),
-            None => sc,
+        if let Some(cause) = error {
+            self.handle_error_nolabel(&device.error_sink, cause, "Device::create_swap_chain");
+        }
+        SwapChain {
+            id: sc,
+            error_sink: Arc::clone(&device.error_sink),
         }
     }
 
@@ -803,26 +1483,9 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &ShaderModuleDescriptor,
     ) -> Self::ShaderModuleId {
-        let global = &self.0;
-        let descriptor = wgc::pipeline::ShaderModuleDescriptor {
-            label: desc.label.map(Borrowed),
-            flags: desc.flags,
-        };
-        let source = match desc.source {
-            ShaderSource::SpirV(ref spv) => wgc::pipeline::ShaderModuleSource::SpirV(Borrowed(spv)),
-            ShaderSource::Wgsl(ref code) => wgc::pipeline::ShaderModuleSource::Wgsl(Borrowed(code)),
-        };
-        let (id, error) = wgc::gfx_select!(
-            device.id => global.device_create_shader_module(device.id, &descriptor, source, PhantomData)
-        );
-        if let Some(cause) = error {
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_shader_module",
-            );
+        let (id, error) = self.device_create_shader_module_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
         id
     }
@@ -832,22 +1495,9 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &BindGroupLayoutDescriptor,
     ) -> Self::BindGroupLayoutId {
-        let global = &self.0;
-        let descriptor = wgc::binding_model::BindGroupLayoutDescriptor {
-            label: desc.label.map(Borrowed),
-            entries: Borrowed(desc.entries),
-        };
-        let (id, error) = wgc::gfx_select!(
-            device.id => global.device_create_bind_group_layout(device.id, &descriptor, PhantomData)
-        );
-        if let Some(cause) = error {
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_bind_group_layout",
-            );
+        let (id, error) = self.device_create_bind_group_layout_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
         id
     }
@@ -857,134 +1507,21 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &BindGroupDescriptor,
     ) -> Self::BindGroupId {
-        use wgc::binding_model as bm;
-
-        let mut arrayed_texture_views = Vec::new();
-        if device
-            .features
-            .contains(Features::SAMPLED_TEXTURE_BINDING_ARRAY)
-        {
-            // gather all the array view IDs first
-            for entry in desc.entries.iter() {
-                if let BindingResource::TextureViewArray(array) = entry.resource {
-                    arrayed_texture_views.extend(array.iter().map(|view| view.id));
-                }
-            }
-        }
-        let mut remaining_arrayed_texture_views = &arrayed_texture_views[..];
-
-        let mut arrayed_buffer_bindings = Vec::new();
-        if device.features.contains(Features::BUFFER_BINDING_ARRAY) {
-            // gather all the buffers first
-            for entry in desc.entries.iter() {
-                if let BindingResource::BufferArray(array) = entry.resource {
-                    arrayed_buffer_bindings.extend(array.iter().map(|binding| bm::BufferBinding {
-                        buffer_id: binding.buffer.id.id,
-                        offset: binding.offset,
-                        size: binding.size,
-                    }));
-                }
-            }
-        }
-        let mut remaining_arrayed_buffer_bindings = &arrayed_buffer_bindings[..];
-
-        let entries = desc
-            .entries
-            .iter()
-            .map(|entry| bm::BindGroupEntry {
-                binding: entry.binding,
-                resource: match entry.resource {
-                    BindingResource::Buffer(BufferBinding {
-                        buffer,
-                        offset,
-                        size,
-                    }) => bm::BindingResource::Buffer(bm::BufferBinding {
-                        buffer_id: buffer.id.id,
-                        offset,
-                        size,
-                    }),
-                    BindingResource::BufferArray(array) => {
-                        let slice = &remaining_arrayed_buffer_bindings[..array.len()];
-                        remaining_arrayed_buffer_bindings =
-                            &remaining_arrayed_buffer_bindings[array.len()..];
-                        bm::BindingResource::BufferArray(Borrowed(slice))
-                    }
-                    BindingResource::Sampler(sampler) => bm::BindingResource::Sampler(sampler.id),
-                    BindingResource::TextureView(texture_view) => {
-                        bm::BindingResource::TextureView(texture_view.id)
-                    }
-                    BindingResource::TextureViewArray(array) => {
-                        let slice = &remaining_arrayed_texture_views[..array.len()];
-                        remaining_arrayed_texture_views =
-                            &remaining_arrayed_texture_views[array.len()..];
-                        bm::BindingResource::TextureViewArray(Borrowed(slice))
-                    }
-                },
-            })
-            .collect::<Vec<_>>();
-        let descriptor = bm::BindGroupDescriptor {
-            label: desc.label.as_ref().map(|label| Borrowed(&label[..])),
-            layout: desc.layout.id,
-            entries: Borrowed(&entries),
-        };
-
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_bind_group(
-            device.id,
-            &descriptor,
-            PhantomData
-        ));
-        if let Some(cause) = error {
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_bind_group",
-            );
+        let (id, error) = self.device_create_bind_group_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
         id
     }
 
     fn device_create_pipeline_layout(
-        &self,
-        device: &Self::DeviceId,
-        desc: &PipelineLayoutDescriptor,
-    ) -> Self::PipelineLayoutId {
-        // Limit is always less or equal to wgc::MAX_BIND_GROUPS, so this is always right
-        // Guards following ArrayVec
-        assert!(
-            desc.bind_group_layouts.len() <= wgc::MAX_BIND_GROUPS,
-            "Bind group layout count {} exceeds device bind group limit {}",
-            desc.bind_group_layouts.len(),
-            wgc::MAX_BIND_GROUPS
-        );
-
-        let temp_layouts = desc
-            .bind_group_layouts
-            .iter()
-            .map(|bgl| bgl.id)
-            .collect::<ArrayVec<[_; wgc::MAX_BIND_GROUPS]>>();
-        let descriptor = wgc::binding_model::PipelineLayoutDescriptor {
-            label: desc.label.map(Borrowed),
-            bind_group_layouts: Borrowed(&temp_layouts),
-            push_constant_ranges: Borrowed(&desc.push_constant_ranges),
-        };
-
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_pipeline_layout(
-            device.id,
-            &descriptor,
-            PhantomData
-        ));
-        if let Some(cause) = error {
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_pipeline_layout",
-            );
+        &self,
+        device: &Self::DeviceId,
+        desc: &PipelineLayoutDescriptor,
+    ) -> Self::PipelineLayoutId {
+        let (id, error) = self.device_create_pipeline_layout_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
         id
     }
@@ -994,68 +1531,9 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &RenderPipelineDescriptor,
     ) -> Self::RenderPipelineId {
-        use wgc::pipeline as pipe;
-
-        let vertex_buffers: ArrayVec<[_; wgc::device::MAX_VERTEX_BUFFERS]> = desc
-            .vertex
-            .buffers
-            .iter()
-            .map(|vbuf| pipe::VertexBufferLayout {
-                array_stride: vbuf.array_stride,
-                step_mode: vbuf.step_mode,
-                attributes: Borrowed(vbuf.attributes),
-            })
-            .collect();
-
-        let implicit_pipeline_ids = match desc.layout {
-            Some(_) => None,
-            None => Some(wgc::device::ImplicitPipelineIds {
-                root_id: PhantomData,
-                group_ids: &[PhantomData; wgc::MAX_BIND_GROUPS],
-            }),
-        };
-        let descriptor = pipe::RenderPipelineDescriptor {
-            label: desc.label.map(Borrowed),
-            layout: desc.layout.map(|l| l.id),
-            vertex: pipe::VertexState {
-                stage: pipe::ProgrammableStageDescriptor {
-                    module: desc.vertex.module.id,
-                    entry_point: Borrowed(desc.vertex.entry_point),
-                },
-                buffers: Borrowed(&vertex_buffers),
-            },
-            primitive: desc.primitive,
-            depth_stencil: desc.depth_stencil.clone(),
-            multisample: desc.multisample,
-            fragment: desc.fragment.as_ref().map(|frag| pipe::FragmentState {
-                stage: pipe::ProgrammableStageDescriptor {
-                    module: frag.module.id,
-                    entry_point: Borrowed(frag.entry_point),
-                },
-                targets: Borrowed(frag.targets),
-            }),
-        };
-
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_render_pipeline(
-            device.id,
-            &descriptor,
-            PhantomData,
-            implicit_pipeline_ids
-        ));
-        if let Some(cause) = error {
-            if let wgc::pipeline::CreateRenderPipelineError::Internal { stage, ref error } = cause {
-                log::warn!("Shader translation error for stage {:?}: {}", stage, error);
-                log::warn!("Please report it to https://github.com/gfx-rs/naga");
-                log::warn!("Try enabling `wgpu/cross` feature as a workaround.");
-            }
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_render_pipeline",
-            );
+        let (id, error) = self.device_create_render_pipeline_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
         id
     }
@@ -1065,48 +1543,9 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &ComputePipelineDescriptor,
     ) -> Self::ComputePipelineId {
-        use wgc::pipeline as pipe;
-
-        let implicit_pipeline_ids = match desc.layout {
-            Some(_) => None,
-            None => Some(wgc::device::ImplicitPipelineIds {
-                root_id: PhantomData,
-                group_ids: &[PhantomData; wgc::MAX_BIND_GROUPS],
-            }),
-        };
-        let descriptor = pipe::ComputePipelineDescriptor {
-            label: desc.label.map(Borrowed),
-            layout: desc.layout.map(|l| l.id),
-            stage: pipe::ProgrammableStageDescriptor {
-                module: desc.module.id,
-                entry_point: Borrowed(desc.entry_point),
-            },
-        };
-
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_compute_pipeline(
-            device.id,
-            &descriptor,
-            PhantomData,
-            implicit_pipeline_ids
-        ));
-        if let Some(cause) = error {
-            if let wgc::pipeline::CreateComputePipelineError::Internal(ref error) = cause {
-                log::warn!(
-                    "Shader translation error for stage {:?}: {}",
-                    wgt::ShaderStage::COMPUTE,
-                    error
-                );
-                log::warn!("Please report it to https://github.com/gfx-rs/naga");
-                log::warn!("Try enabling `wgpu/cross` feature as a workaround.");
-            }
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_compute_pipeline",
-            );
+        let (id, error) = self.device_create_compute_pipeline_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
         id
     }
@@ -1116,25 +1555,11 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &crate::BufferDescriptor<'_>,
     ) -> Self::BufferId {
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_buffer(
-            device.id,
-            &desc.map_label(|l| l.map(Borrowed)),
-            PhantomData
-        ));
-        if let Some(cause) = error {
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_buffer",
-            );
-        }
-        Buffer {
-            id,
-            error_sink: Arc::clone(&device.error_sink),
+        let (id, error) = self.device_create_buffer_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
+        id
     }
 
     fn device_create_texture(
@@ -1142,25 +1567,11 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &TextureDescriptor,
     ) -> Self::TextureId {
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_texture(
-            device.id,
-            &desc.map_label(|l| l.map(Borrowed)),
-            PhantomData
-        ));
-        if let Some(cause) = error {
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_texture",
-            );
-        }
-        Texture {
-            id,
-            error_sink: Arc::clone(&device.error_sink),
+        let (id, error) = self.device_create_texture_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
+        id
     }
 
     fn device_create_sampler(
@@ -1168,37 +1579,9 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &SamplerDescriptor,
     ) -> Self::SamplerId {
-        let descriptor = wgc::resource::SamplerDescriptor {
-            label: desc.label.map(Borrowed),
-            address_modes: [
-                desc.address_mode_u,
-                desc.address_mode_v,
-                desc.address_mode_w,
-            ],
-            mag_filter: desc.mag_filter,
-            min_filter: desc.min_filter,
-            mipmap_filter: desc.mipmap_filter,
-            lod_min_clamp: desc.lod_min_clamp,
-            lod_max_clamp: desc.lod_max_clamp,
-            compare: desc.compare,
-            anisotropy_clamp: desc.anisotropy_clamp,
-            border_color: desc.border_color,
-        };
-
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_sampler(
-            device.id,
-            &descriptor,
-            PhantomData
-        ));
-        if let Some(cause) = error {
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_sampler",
-            );
+        let (id, error) = self.device_create_sampler_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
         id
     }
@@ -1208,14 +1591,9 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &wgt::QuerySetDescriptor,
     ) -> Self::QuerySetId {
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_query_set(
-            device.id,
-            &desc,
-            PhantomData
-        ));
-        if let Some(cause) = error {
-            self.handle_error_nolabel(&device.error_sink, cause, "Device::create_query_set");
+        let (id, error) = self.device_create_query_set_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
         id
     }
@@ -1225,26 +1603,11 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &CommandEncoderDescriptor,
     ) -> Self::CommandEncoderId {
-        let global = &self.0;
-        let (id, error) = wgc::gfx_select!(device.id => global.device_create_command_encoder(
-            device.id,
-            &desc.map_label(|l| l.map(Borrowed)),
-            PhantomData
-        ));
-        if let Some(cause) = error {
-            self.handle_error(
-                &device.error_sink,
-                cause,
-                LABEL,
-                desc.label,
-                "Device::create_command_encoder",
-            );
-        }
-        CommandEncoder {
-            id,
-            error_sink: Arc::clone(&device.error_sink),
-            open: true,
+        let (encoder, error) = self.device_create_command_encoder_with_error(device, desc);
+        if let Some(error) = error {
+            self.report_error(&device.error_sink, error);
         }
+        encoder
     }
 
     fn device_create_render_bundle_encoder(
@@ -1258,19 +1621,38 @@ impl crate::Context for Context {
             depth_stencil_format: desc.depth_stencil_format,
             sample_count: desc.sample_count,
         };
-        match wgc::command::RenderBundleEncoder::new(&descriptor, device.id, None) {
-            Ok(id) => id,
-            Err(e) => panic!("Error in Device::create_render_bundle_encoder: {}", e),
+        let encoder = match wgc::command::RenderBundleEncoder::new(&descriptor, device.id, None) {
+            Ok(encoder) => Some(encoder),
+            Err(cause) => {
+                self.handle_error(
+                    &device.error_sink,
+                    cause,
+                    LABEL,
+                    desc.label,
+                    "Device::create_render_bundle_encoder",
+                );
+                None
+            }
+        };
+        RenderBundleEncoder {
+            encoder,
+            error_sink: Arc::clone(&device.error_sink),
         }
     }
 
     fn device_drop(&self, device: &Self::DeviceId) {
+        self.2.lock().retain(|&(id, _)| id != device.id);
         #[cfg(not(target_arch = "wasm32"))]
         {
             let global = &self.0;
             match wgc::gfx_select!(device.id => global.device_poll(device.id, true)) {
                 Ok(()) => (),
-                Err(err) => self.handle_error_fatal(err, "Device::drop"),
+                Err(cause) => {
+                    // A lost device has nothing left to wait for.
+                    if !is_device_lost(&cause) {
+                        self.handle_error_nolabel(&device.error_sink, cause, "Device::drop")
+                    }
+                }
             }
         }
         //TODO: make this work in general
@@ -1292,7 +1674,7 @@ impl crate::Context for Context {
             }
         )) {
             Ok(()) => (),
-            Err(err) => self.handle_error_fatal(err, "Device::poll"),
+            Err(cause) => self.handle_error_nolabel(&device.error_sink, cause, "Device::poll"),
         }
     }
 
@@ -1305,48 +1687,82 @@ impl crate::Context for Context {
         error_sink.uncaptured_handler = Box::new(handler);
     }
 
-    fn buffer_map_async(
+    fn device_push_error_scope(&self, device: &Self::DeviceId, filter: crate::ErrorFilter) {
+        let mut error_sink = device.error_sink.lock();
+        error_sink.scopes.push(ErrorScope {
+            error: None,
+            filter,
+        });
+    }
+
+    fn device_pop_error_scope(&self, device: &Self::DeviceId) -> Self::PopErrorScopeFuture {
+        let mut error_sink = device.error_sink.lock();
+        ready(
+            error_sink
+                .scopes
+                .pop()
+                .map(|scope| scope.error)
+                .ok_or(crate::PopErrorScopeError),
+        )
+    }
+
+    fn device_lost(&self, device: &Self::DeviceId) -> Self::DeviceLostFuture {
+        let (future, completion) = native_gpu_future::new_gpu_future();
+        let mut error_sink = device.error_sink.lock();
+        match error_sink.lost {
+            DeviceLostState::Alive(ref mut completions) => completions.push(completion),
+            DeviceLostState::Lost(ref info) => completion.complete(info.clone()),
+        }
+        future
+    }
+
+    fn buffer_map_async_with(
         &self,
         buffer: &Self::BufferId,
         mode: MapMode,
         range: Range<wgt::BufferAddress>,
-    ) -> Self::MapAsyncFuture {
-        let (future, completion) = native_gpu_future::new_gpu_future();
-
-        extern "C" fn buffer_map_future_wrapper(
+        callback: impl FnOnce(Result<(), crate::BufferAsyncError>) + Send + 'static,
+    ) {
+        extern "C" fn buffer_map_callback_wrapper(
             status: wgc::resource::BufferMapAsyncStatus,
             user_data: *mut u8,
         ) {
-            let completion =
-                unsafe { native_gpu_future::GpuFutureCompletion::from_raw(user_data as _) };
-            completion.complete(match status {
+            let callback = unsafe { Box::from_raw(user_data as *mut BufferMapCallback) };
+            callback(match status {
                 wgc::resource::BufferMapAsyncStatus::Success => Ok(()),
                 _ => Err(crate::BufferAsyncError),
             })
         }
 
+        let callback: BufferMapCallback = Box::new(callback);
+        let user_data = Box::into_raw(Box::new(callback));
         let operation = wgc::resource::BufferMapOperation {
             host: match mode {
                 MapMode::Read => wgc::device::HostMap::Read,
                 MapMode::Write => wgc::device::HostMap::Write,
             },
-            callback: buffer_map_future_wrapper,
-            user_data: completion.into_raw() as _,
+            callback: buffer_map_callback_wrapper,
+            user_data: user_data as _,
         };
 
         let global = &self.0;
         match wgc::gfx_select!(buffer.id => global.buffer_map_async(buffer.id, range, operation)) {
             Ok(()) => (),
-            Err(cause) => self.handle_error_nolabel(&buffer.error_sink, cause, "Buffer::map_async"),
+            Err(cause) => {
+                self.handle_error_nolabel(&buffer.error_sink, cause, "Buffer::map_async");
+                // The operation is rejected without invoking its callback,
+                // so resolve it here instead of leaving it pending forever.
+                let callback = unsafe { Box::from_raw(user_data) };
+                callback(Err(crate::BufferAsyncError));
+            }
         }
-        future
     }
 
     fn buffer_get_mapped_range(
         &self,
         buffer: &Self::BufferId,
         sub_range: Range<wgt::BufferAddress>,
-    ) -> BufferMappedRange {
+    ) -> Result<BufferMappedRange, crate::MapError> {
         let size = sub_range.end - sub_range.start;
         let global = &self.0;
         match wgc::gfx_select!(buffer.id => global.buffer_get_mapped_range(
@@ -1354,11 +1770,14 @@ impl crate::Context for Context {
             sub_range.start,
             Some(size)
         )) {
-            Ok((ptr, size)) => BufferMappedRange {
+            Ok((ptr, size)) => Ok(BufferMappedRange {
                 ptr,
                 size: size as usize,
-            },
-            Err(err) => self.handle_error_fatal(err, "Buffer::get_mapped_range"),
+            }),
+            Err(cause) => {
+                let error = self.make_error(cause, "", None, "Buffer::get_mapped_range");
+                Err(crate::MapError::Backend(error))
+            }
         }
     }
 
@@ -1381,17 +1800,22 @@ impl crate::Context for Context {
         Self::SwapChainOutputDetail,
     ) {
         let global = &self.0;
+        let detail = SwapChainOutputDetail {
+            swap_chain_id: swap_chain.id,
+            error_sink: Arc::clone(&swap_chain.error_sink),
+        };
         match wgc::gfx_select!(
-            *swap_chain => global.swap_chain_get_current_texture_view(*swap_chain, PhantomData)
+            swap_chain.id => global.swap_chain_get_current_texture_view(swap_chain.id, PhantomData)
         ) {
-            Ok(wgc::swap_chain::SwapChainOutput { status, view_id }) => (
-                view_id,
-                status,
-                SwapChainOutputDetail {
-                    swap_chain_id: *swap_chain,
-                },
-            ),
-            Err(err) => self.handle_error_fatal(err, "SwapChain::get_current_texture_view"),
+            Ok(wgc::swap_chain::SwapChainOutput { status, view_id }) => (view_id, status, detail),
+            Err(cause) => {
+                self.handle_error_nolabel(
+                    &swap_chain.error_sink,
+                    cause,
+                    "SwapChain::get_current_texture_view",
+                );
+                (None, SwapChainStatus::Lost, detail)
+            }
         }
     }
 
@@ -1399,7 +1823,9 @@ impl crate::Context for Context {
         let global = &self.0;
         match wgc::gfx_select!(*view => global.swap_chain_present(detail.swap_chain_id)) {
             Ok(_status) => (),
-            Err(err) => self.handle_error_fatal(err, "SwapChain::present"),
+            Err(cause) => {
+                self.handle_error_nolabel(&detail.error_sink, cause, "SwapChain::present")
+            }
         }
     }
 
@@ -1454,6 +1880,7 @@ impl crate::Context for Context {
         }
     }
     fn buffer_drop(&self, buffer: &Self::BufferId) {
+        self.1.forget(buffer);
         let global = &self.0;
         wgc::gfx_select!(buffer.id => global.buffer_drop(buffer.id, false))
     }
@@ -1465,10 +1892,12 @@ impl crate::Context for Context {
         }
     }
     fn texture_drop(&self, texture: &Self::TextureId) {
+        self.1.forget(texture);
         let global = &self.0;
         wgc::gfx_select!(texture.id => global.texture_drop(texture.id, false))
     }
     fn texture_view_drop(&self, texture_view: &Self::TextureViewId) {
+        self.1.forget(texture_view);
         let global = &self.0;
         match wgc::gfx_select!(*texture_view => global.texture_view_drop(*texture_view, false)) {
             Ok(()) => (),
@@ -1476,26 +1905,32 @@ impl crate::Context for Context {
         }
     }
     fn sampler_drop(&self, sampler: &Self::SamplerId) {
+        self.1.forget(sampler);
         let global = &self.0;
         wgc::gfx_select!(*sampler => global.sampler_drop(*sampler))
     }
     fn query_set_drop(&self, query_set: &Self::QuerySetId) {
+        self.1.forget(query_set);
         let global = &self.0;
         wgc::gfx_select!(*query_set => global.query_set_drop(*query_set))
     }
     fn bind_group_drop(&self, bind_group: &Self::BindGroupId) {
+        self.1.forget(bind_group);
         let global = &self.0;
         wgc::gfx_select!(*bind_group => global.bind_group_drop(*bind_group))
     }
     fn bind_group_layout_drop(&self, bind_group_layout: &Self::BindGroupLayoutId) {
+        self.1.forget(bind_group_layout);
         let global = &self.0;
         wgc::gfx_select!(*bind_group_layout => global.bind_group_layout_drop(*bind_group_layout))
     }
     fn pipeline_layout_drop(&self, pipeline_layout: &Self::PipelineLayoutId) {
+        self.1.forget(pipeline_layout);
         let global = &self.0;
         wgc::gfx_select!(*pipeline_layout => global.pipeline_layout_drop(*pipeline_layout))
     }
     fn shader_module_drop(&self, shader_module: &Self::ShaderModuleId) {
+        self.1.forget(shader_module);
         let global = &self.0;
         wgc::gfx_select!(*shader_module => global.shader_module_drop(*shader_module))
     }
@@ -1511,13 +1946,17 @@ impl crate::Context for Context {
     }
     fn render_bundle_drop(&self, render_bundle: &Self::RenderBundleId) {
         let global = &self.0;
-        wgc::gfx_select!(*render_bundle => global.render_bundle_drop(*render_bundle))
+        if let Some(render_bundle) = *render_bundle {
+            wgc::gfx_select!(render_bundle => global.render_bundle_drop(render_bundle))
+        }
     }
     fn compute_pipeline_drop(&self, pipeline: &Self::ComputePipelineId) {
+        self.1.forget(pipeline);
         let global = &self.0;
         wgc::gfx_select!(*pipeline => global.compute_pipeline_drop(*pipeline))
     }
     fn render_pipeline_drop(&self, pipeline: &Self::RenderPipelineId) {
+        self.1.forget(pipeline);
         let global = &self.0;
         wgc::gfx_select!(*pipeline => global.render_pipeline_drop(*pipeline))
     }
@@ -1823,16 +2262,25 @@ impl crate::Context for Context {
         encoder: Self::RenderBundleEncoderId,
         desc: &crate::RenderBundleDescriptor,
     ) -> Self::RenderBundleId {
+        // The creation error of the encoder has already been reported
+        let error_sink = encoder.error_sink;
+        let encoder = encoder.encoder?;
         let global = &self.0;
         let (id, error) = wgc::gfx_select!(encoder.parent() => global.render_bundle_encoder_finish(
             encoder,
             &desc.map_label(|l| l.map(Borrowed)),
             PhantomData
         ));
-        if let Some(err) = error {
-            self.handle_error_fatal(err, "RenderBundleEncoder::finish");
+        if let Some(cause) = error {
+            self.handle_error(
+                &error_sink,
+                cause,
+                LABEL,
+                desc.label,
+                "RenderBundleEncoder::finish",
+            );
         }
-        id
+        Some(id)
     }
 
     fn queue_write_buffer(
@@ -1913,23 +2361,83 @@ impl crate::Context for Context {
 #[derive(Debug)]
 pub(crate) struct SwapChainOutputDetail {
     swap_chain_id: wgc::id::SwapChainId,
+    error_sink: ErrorSink,
 }
 
 type ErrorSink = Arc<Mutex<ErrorSinkRaw>>;
 
+struct ErrorScope {
+    error: Option<crate::Error>,
+    filter: crate::ErrorFilter,
+}
+
+enum DeviceLostState {
+    Alive(Vec<native_gpu_future::GpuFutureCompletion<crate::DeviceLostInfo>>),
+    Lost(crate::DeviceLostInfo),
+}
+
 struct ErrorSinkRaw {
+    scopes: Vec<ErrorScope>,
     uncaptured_handler: Box<dyn crate::UncapturedErrorHandler>,
+    lost: DeviceLostState,
 }
 
 impl ErrorSinkRaw {
     fn new() -> ErrorSinkRaw {
         ErrorSinkRaw {
+            scopes: Vec::new(),
             uncaptured_handler: Box::from(default_error_handler),
+            lost: DeviceLostState::Alive(Vec::new()),
         }
     }
 
-    fn handle_error(&self, err: crate::Error) {
-        (self.uncaptured_handler)(err);
+    /// Signals the loss of the device if `error` was caused by it, returning whether it was.
+    fn check_device_lost(&mut self, error: &crate::Error) -> bool {
+        if !is_device_lost(error) {
+            return false;
+        }
+        let message = match error.source() {
+            Some(source) => source.to_string(),
+            None => error.to_string(),
+        };
+        self.device_lost(message);
+        true
+    }
+
+    fn device_lost(&mut self, message: String) {
+        let info = crate::DeviceLostInfo {
+            reason: crate::DeviceLostReason::Unknown,
+            message,
+        };
+        if let DeviceLostState::Alive(completions) =
+            std::mem::replace(&mut self.lost, DeviceLostState::Lost(info.clone()))
+        {
+            for completion in completions {
+                completion.complete(info.clone());
+            }
+        }
+    }
+
+    fn handle_error(&mut self, err: crate::Error) {
+        let filter = match err {
+            crate::Error::OutOfMemoryError { .. } => crate::ErrorFilter::OutOfMemory,
+            crate::Error::ValidationError { .. } => crate::ErrorFilter::Validation,
+        };
+        match self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.filter == filter)
+        {
+            Some(scope) => {
+                if scope.error.is_none() {
+                    scope.error = Some(err);
+                }
+            }
+            None => {
+                (self.uncaptured_handler)(err);
+            }
+        }
     }
 }
 
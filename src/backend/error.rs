@@ -1,4 +1,5 @@
-use std::{error::Error, fmt};
+use parking_lot::Mutex;
+use std::{any::TypeId, collections::HashMap, error::Error, fmt, panic::Location};
 
 #[derive(Debug)]
 pub(super) struct ContextError {
@@ -6,6 +7,7 @@ pub(super) struct ContextError {
     pub cause: Box<dyn Error + Send + Sync + 'static>,
     pub label_key: &'static str,
     pub label: String,
+    pub report: crate::ErrorReport,
 }
 
 impl fmt::Display for ContextError {
@@ -20,20 +22,244 @@ impl Error for ContextError {
     }
 }
 
+pub(crate) type SiteKey = (crate::ResourceType, crate::ResourceId);
+
+/// Resource id that can be associated with its creation site.
+pub(crate) trait TrackedId {
+    fn resource_type(&self) -> crate::ResourceType;
+    fn resource_id(&self) -> crate::ResourceId;
+
+    fn site_key(&self) -> SiteKey {
+        (self.resource_type(), self.resource_id())
+    }
+}
+
+impl<T: 'static> TrackedId for wgc::id::Id<T> {
+    fn resource_type(&self) -> crate::ResourceType {
+        use crate::ResourceType as Rt;
+        use wgc::id;
+
+        let ty = TypeId::of::<Self>();
+        let types = [
+            (TypeId::of::<id::BufferId>(), Rt::Buffer),
+            (TypeId::of::<id::TextureId>(), Rt::Texture),
+            (TypeId::of::<id::TextureViewId>(), Rt::TextureView),
+            (TypeId::of::<id::SamplerId>(), Rt::Sampler),
+            (TypeId::of::<id::ShaderModuleId>(), Rt::ShaderModule),
+            (TypeId::of::<id::BindGroupLayoutId>(), Rt::BindGroupLayout),
+            (TypeId::of::<id::BindGroupId>(), Rt::BindGroup),
+            (TypeId::of::<id::PipelineLayoutId>(), Rt::PipelineLayout),
+            (TypeId::of::<id::RenderPipelineId>(), Rt::RenderPipeline),
+            (TypeId::of::<id::ComputePipelineId>(), Rt::ComputePipeline),
+            (TypeId::of::<id::CommandBufferId>(), Rt::CommandBuffer),
+            (TypeId::of::<id::RenderBundleId>(), Rt::RenderBundle),
+            (TypeId::of::<id::QuerySetId>(), Rt::QuerySet),
+        ];
+        types
+            .iter()
+            .find(|&&(type_id, _)| type_id == ty)
+            .map_or(Rt::Other, |&(_, resource_type)| resource_type)
+    }
+
+    fn resource_id(&self) -> crate::ResourceId {
+        use wgc::id::TypedId as _;
+        let (index, epoch, backend) = self.unzip();
+        crate::ResourceId {
+            index,
+            epoch,
+            backend,
+        }
+    }
+}
+
+/// Locations in user code where resources were created.
+///
+/// Only filled in with the `track-caller` feature.
+#[derive(Debug, Default)]
+pub(super) struct CreationSites {
+    sites: Mutex<HashMap<SiteKey, &'static Location<'static>>>,
+}
+
+impl CreationSites {
+    #[cfg(feature = "track-caller")]
+    pub(super) fn record(&self, id: &impl TrackedId, location: &'static Location<'static>) {
+        self.sites.lock().insert(id.site_key(), location);
+    }
+
+    pub(super) fn forget(&self, id: &impl TrackedId) {
+        if cfg!(feature = "track-caller") {
+            self.sites.lock().remove(&id.site_key());
+        }
+    }
+
+    pub(super) fn get(&self, id: &impl TrackedId) -> Option<&'static Location<'static>> {
+        if cfg!(feature = "track-caller") {
+            self.sites.lock().get(&id.site_key()).copied()
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn error_operation(error: &(dyn Error + 'static)) -> Option<&str> {
+    error
+        .downcast_ref::<ContextError>()
+        .map(|context_error| context_error.string)
+}
+
+pub(crate) fn error_label(error: &(dyn Error + 'static)) -> Option<&str> {
+    error
+        .downcast_ref::<ContextError>()
+        .map(|context_error| context_error.label.as_str())
+        .filter(|label| !label.is_empty())
+}
+
+pub(crate) fn error_report(error: &(dyn Error + 'static)) -> Option<&crate::ErrorReport> {
+    error
+        .downcast_ref::<ContextError>()
+        .map(|context_error| &context_error.report)
+}
+
+pub(crate) fn error_kind(error: &(dyn Error + 'static)) -> crate::ErrorKind {
+    let mut source_opt = Some(error);
+    while let Some(source) = source_opt {
+        if let Some(kind) = kind_any(source) {
+            return kind;
+        }
+        source_opt = source.source();
+    }
+    crate::ErrorKind::Other
+}
+
+fn kind_any(error: &(dyn Error + 'static)) -> Option<crate::ErrorKind> {
+    use crate::ErrorKind as Ek;
+    use wgc::{binding_model as bm, command as cmd, pipeline as pipe, resource as res};
+
+    if error.is::<res::CreateBufferError>() || error.is::<res::BufferAccessError>() {
+        return Some(Ek::Buffer);
+    }
+    if error.is::<res::CreateTextureError>() || error.is::<res::CreateTextureViewError>() {
+        return Some(Ek::Texture);
+    }
+    if error.is::<res::CreateSamplerError>() {
+        return Some(Ek::Sampler);
+    }
+    if error.is::<pipe::CreateShaderModuleError>() {
+        return Some(Ek::ShaderModule);
+    }
+    if error.is::<bm::CreateBindGroupLayoutError>() {
+        return Some(Ek::BindGroupLayout);
+    }
+    if error.is::<bm::CreateBindGroupError>() {
+        return Some(Ek::BindGroup);
+    }
+    if error.is::<bm::CreatePipelineLayoutError>() {
+        return Some(Ek::PipelineLayout);
+    }
+    if error.is::<pipe::CreateRenderPipelineError>()
+        || error.is::<pipe::CreateComputePipelineError>()
+    {
+        return Some(Ek::Pipeline);
+    }
+    if error.is::<cmd::RenderPassError>() || error.is::<cmd::ComputePassError>() {
+        return Some(Ek::Pass);
+    }
+    if error.is::<cmd::RenderBundleError>() {
+        return Some(Ek::RenderBundle);
+    }
+    if error.is::<cmd::TransferError>() {
+        return Some(Ek::Transfer);
+    }
+    if error.is::<res::CreateQuerySetError>() || error.is::<cmd::QueryError>() {
+        return Some(Ek::Query);
+    }
+    if error.is::<cmd::CommandEncoderError>() {
+        return Some(Ek::CommandEncoder);
+    }
+    None
+}
+
 impl super::Context {
-    pub(super) fn format_error(&self, err: &(impl Error + 'static)) -> String {
-        let mut err_descs = vec![self.format_pretty_any(err)];
+    pub(super) fn format_error(
+        &self,
+        err: &(impl Error + 'static),
+    ) -> (String, crate::ErrorReport) {
+        let mut fmt = ErrorFormatter {
+            context: self,
+            text: String::new(),
+            report: crate::ErrorReport::default(),
+        };
+        fmt.pretty_any(err);
 
         let mut source_opt = err.source();
         while let Some(source) = source_opt {
-            err_descs.push(self.format_pretty_any(source));
+            fmt.pretty_any(source);
             source_opt = source.source();
         }
 
-        format!("Validation Error\n\nCaused by:\n{}", err_descs.join(""))
+        (
+            format!("Validation Error\n\nCaused by:\n{}", fmt.text),
+            fmt.report,
+        )
+    }
+}
+
+/// Collects both the text description of an error chain and its
+/// structured [`crate::ErrorReport`].
+pub struct ErrorFormatter<'a> {
+    context: &'a super::Context,
+    text: String,
+    report: crate::ErrorReport,
+}
+
+impl<'a> ErrorFormatter<'a> {
+    fn global(&self) -> &'a wgc::hub::Global<wgc::hub::IdentityManagerFactory> {
+        self.context.global()
     }
 
-    fn format_pretty_any(&self, error: &(dyn Error + 'static)) -> String {
+    fn error(&mut self, err: &dyn fmt::Display) {
+        let message = err.to_string();
+        self.text.push_str(&format_error_line(&message));
+        self.report.chain.push(crate::ErrorReportEntry {
+            message,
+            resources: Vec::new(),
+        });
+    }
+
+    fn label(&mut self, label_key: &str, label_value: &str) {
+        self.push_resource(label_key, label_value, None, None);
+    }
+
+    fn resource(&mut self, label_key: &str, label_value: &str, id: &impl TrackedId) {
+        let location = self.context.creation_site(id);
+        let resource = (id.resource_type(), id.resource_id());
+        self.push_resource(label_key, label_value, Some(resource), location);
+    }
+
+    fn push_resource(
+        &mut self,
+        label_key: &str,
+        label_value: &str,
+        resource: Option<(crate::ResourceType, crate::ResourceId)>,
+        location: Option<&'static Location<'static>>,
+    ) {
+        self.text
+            .push_str(&format_label_line(label_key, label_value, location));
+        if label_key.is_empty() {
+            return;
+        }
+        if let Some(entry) = self.report.chain.last_mut() {
+            entry.resources.push(crate::ErrorResource {
+                role: label_key.to_string(),
+                resource_type: resource.map(|(resource_type, _)| resource_type),
+                label: label_value.to_string(),
+                id: resource.map(|(_, id)| id),
+                created_at: location.map(ToString::to_string),
+            });
+        }
+    }
+
+    fn pretty_any(&mut self, error: &(dyn Error + 'static)) {
         if let Some(pretty_err) = error.downcast_ref::<ContextError>() {
             return pretty_err.fmt_pretty(self);
         }
@@ -71,7 +297,7 @@ impl super::Context {
         }
 
         // default
-        format_error_line(error.as_display())
+        self.error(error.as_display())
     }
 }
 
@@ -83,11 +309,24 @@ pub(super) fn format_note_line(note: &dyn fmt::Display) -> String {
     format!("      note: {}\n", note)
 }
 
-pub(super) fn format_label_line(label_key: &str, label_value: &str) -> String {
-    if label_key.is_empty() || label_value.is_empty() {
-        String::new()
-    } else {
-        format_note_line(&format!("{} = `{}`", label_key, label_value))
+pub(super) fn format_label_line(
+    label_key: &str,
+    label_value: &str,
+    location: Option<&Location>,
+) -> String {
+    if label_key.is_empty() {
+        return String::new();
+    }
+    match (label_value.is_empty(), location) {
+        (true, None) => String::new(),
+        (false, None) => format_note_line(&format!("{} = `{}`", label_key, label_value)),
+        (true, Some(location)) => {
+            format_note_line(&format!("{} created at {}", label_key, location))
+        }
+        (false, Some(location)) => format_note_line(&format!(
+            "{} = `{}`, created at {}",
+            label_key, label_value, location
+        )),
     }
 }
 
@@ -102,232 +341,225 @@ impl<T: fmt::Display> AsDisplay for T {
 }
 
 pub trait PrettyError: Error {
-    fn fmt_pretty(&self, _context: &super::Context) -> String {
-        format_error_line(self.as_display())
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        fmt.error(self.as_display());
     }
 }
 
 impl PrettyError for ContextError {
-    fn fmt_pretty(&self, _context: &super::Context) -> String {
-        format_error_line(self.as_display()) + &format_label_line(self.label_key, &self.label)
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        fmt.error(self);
+        fmt.label(self.label_key, &self.label);
     }
 }
 
 impl PrettyError for wgc::command::RenderCommandError {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
-        let global = context.global();
-        let mut ret = format_error_line(self);
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        let global = fmt.global();
+        fmt.error(self);
         match *self {
             Self::InvalidBindGroup(id) => {
                 let name = wgc::gfx_select!(id => global.bind_group_label(id));
-                ret.push_str(&format_label_line("bind group", &name));
+                fmt.resource("bind group", &name, &id);
             }
             Self::InvalidPipeline(id) => {
                 let name = wgc::gfx_select!(id => global.render_pipeline_label(id));
-                ret.push_str(&format_label_line("render pipeline", &name));
+                fmt.resource("render pipeline", &name, &id);
             }
             Self::Buffer(id, ..) | Self::DestroyedBuffer(id) => {
                 let name = wgc::gfx_select!(id => global.buffer_label(id));
-                ret.push_str(&format_label_line("buffer", &name));
+                fmt.resource("buffer", &name, &id);
             }
             _ => {}
         };
-        ret
     }
 }
 impl PrettyError for wgc::binding_model::CreateBindGroupError {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
-        let global = context.global();
-        let mut ret = format_error_line(self);
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        let global = fmt.global();
+        fmt.error(self);
         match *self {
             Self::InvalidBuffer(id) => {
                 let name = wgc::gfx_select!(id => global.buffer_label(id));
-                ret.push_str(&format_label_line("buffer", &name));
+                fmt.resource("buffer", &name, &id);
             }
             Self::InvalidTextureView(id) => {
                 let name = wgc::gfx_select!(id => global.texture_view_label(id));
-                ret.push_str(&format_label_line("texture view", &name));
+                fmt.resource("texture view", &name, &id);
             }
             Self::InvalidSampler(id) => {
                 let name = wgc::gfx_select!(id => global.sampler_label(id));
-                ret.push_str(&format_label_line("sampler", &name));
+                fmt.resource("sampler", &name, &id);
             }
             _ => {}
         };
-        ret
     }
 }
 
 impl PrettyError for wgc::binding_model::CreatePipelineLayoutError {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
-        let global = context.global();
-        let mut ret = format_error_line(self);
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        let global = fmt.global();
+        fmt.error(self);
         if let Self::InvalidBindGroupLayout(id) = *self {
             let name = wgc::gfx_select!(id => global.bind_group_layout_label(id));
-            ret.push_str(&format_label_line("bind group layout", &name));
+            fmt.resource("bind group layout", &name, &id);
         };
-        ret
     }
 }
 
 impl PrettyError for wgc::command::ExecutionError {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
-        let global = context.global();
-        let mut ret = format_error_line(self);
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        let global = fmt.global();
+        fmt.error(self);
         match *self {
             Self::DestroyedBuffer(id) => {
                 let name = wgc::gfx_select!(id => global.buffer_label(id));
-                ret.push_str(&format_label_line("buffer", &name));
+                fmt.resource("buffer", &name, &id);
             }
             Self::Unimplemented(_reason) => {}
         };
-        ret
     }
 }
 
 impl PrettyError for wgc::command::RenderPassErrorInner {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
-        let global = context.global();
-        let mut ret = format_error_line(self);
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        let global = fmt.global();
+        fmt.error(self);
         if let Self::InvalidAttachment(id) = *self {
             let name = wgc::gfx_select!(id => global.texture_view_label(id));
-            ret.push_str(&format_label_line("attachment", &name));
+            fmt.resource("attachment", &name, &id);
         };
-        ret
     }
 }
 
 impl PrettyError for wgc::command::RenderPassError {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
         // This error is wrapper for the inner error,
         // but the scope has useful labels
-        format_error_line(self) + &self.scope.fmt_pretty(context)
+        fmt.error(self);
+        self.scope.fmt_pretty(fmt);
     }
 }
 
 impl PrettyError for wgc::command::ComputePassError {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
         // This error is wrapper for the inner error,
         // but the scope has useful labels
-        format_error_line(self) + &self.scope.fmt_pretty(context)
+        fmt.error(self);
+        self.scope.fmt_pretty(fmt);
     }
 }
 impl PrettyError for wgc::command::RenderBundleError {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
         // This error is wrapper for the inner error,
         // but the scope has useful labels
-        format_error_line(self) + &self.scope.fmt_pretty(context)
+        fmt.error(self);
+        self.scope.fmt_pretty(fmt);
     }
 }
 
 impl PrettyError for wgc::command::ComputePassErrorInner {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
-        let global = context.global();
-        let mut ret = format_error_line(self);
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        let global = fmt.global();
+        fmt.error(self);
         match *self {
             Self::InvalidBindGroup(id) => {
                 let name = wgc::gfx_select!(id => global.bind_group_label(id));
-                ret.push_str(&format_label_line("bind group", &name));
+                fmt.resource("bind group", &name, &id);
             }
             Self::InvalidPipeline(id) => {
                 let name = wgc::gfx_select!(id => global.compute_pipeline_label(id));
-                ret.push_str(&format_label_line("pipeline", &name));
+                fmt.resource("pipeline", &name, &id);
             }
             Self::InvalidIndirectBuffer(id) => {
                 let name = wgc::gfx_select!(id => global.buffer_label(id));
-                ret.push_str(&format_label_line("indirect buffer", &name));
+                fmt.resource("indirect buffer", &name, &id);
             }
             _ => {}
         };
-        ret
     }
 }
 
 impl PrettyError for wgc::command::TransferError {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
-        let global = context.global();
-        let mut ret = format_error_line(self);
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        let global = fmt.global();
+        fmt.error(self);
         match *self {
             Self::InvalidBuffer(id) => {
                 let name = wgc::gfx_select!(id => global.buffer_label(id));
-                ret.push_str(&format_label_line("label", &name));
+                fmt.resource("label", &name, &id);
             }
             Self::InvalidTexture(id) => {
                 let name = wgc::gfx_select!(id => global.texture_label(id));
-                ret.push_str(&format_label_line("texture", &name));
+                fmt.resource("texture", &name, &id);
             }
             // Self::MissingCopySrcUsageFlag(buf_opt, tex_opt) => {
             //     if let Some(buf) = buf_opt {
             //         let name = wgc::gfx_select!(buf => global.buffer_label(buf));
-            //         ret.push_str(&format_label_line("source", &name));
+            //         fmt.resource("source", &name, &buf);
             //     }
             //     if let Some(tex) = tex_opt {
             //         let name = wgc::gfx_select!(tex => global.texture_label(tex));
-            //         ret.push_str(&format_label_line("source", &name));
+            //         fmt.resource("source", &name, &tex);
             //     }
             // }
             Self::MissingCopyDstUsageFlag(buf_opt, tex_opt) => {
                 if let Some(buf) = buf_opt {
                     let name = wgc::gfx_select!(buf => global.buffer_label(buf));
-                    ret.push_str(&format_label_line("destination", &name));
+                    fmt.resource("destination", &name, &buf);
                 }
                 if let Some(tex) = tex_opt {
                     let name = wgc::gfx_select!(tex => global.texture_label(tex));
-                    ret.push_str(&format_label_line("destination", &name));
+                    fmt.resource("destination", &name, &tex);
                 }
             }
             _ => {}
         };
-        ret
     }
 }
 
 impl PrettyError for wgc::command::PassErrorScope {
-    fn fmt_pretty(&self, context: &super::Context) -> String {
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
         // This error is not in the error chain, only notes are needed
-        let global = context.global();
+        let global = fmt.global();
         match *self {
             Self::Pass(id) => {
                 let name = wgc::gfx_select!(id => global.command_buffer_label(id));
-                format_label_line("command buffer", &name)
+                fmt.resource("command buffer", &name, &id);
             }
             Self::SetBindGroup(id) => {
                 let name = wgc::gfx_select!(id => global.bind_group_label(id));
-                format_label_line("bind group", &name)
+                fmt.resource("bind group", &name, &id);
             }
             Self::SetPipelineRender(id) => {
                 let name = wgc::gfx_select!(id => global.render_pipeline_label(id));
-                format_label_line("render pipeline", &name)
+                fmt.resource("render pipeline", &name, &id);
             }
             Self::SetPipelineCompute(id) => {
                 let name = wgc::gfx_select!(id => global.compute_pipeline_label(id));
-                format_label_line("compute pipeline", &name)
+                fmt.resource("compute pipeline", &name, &id);
             }
             Self::SetVertexBuffer(id) => {
                 let name = wgc::gfx_select!(id => global.buffer_label(id));
-                format_label_line("buffer", &name)
+                fmt.resource("buffer", &name, &id);
             }
             Self::SetIndexBuffer(id) => {
                 let name = wgc::gfx_select!(id => global.buffer_label(id));
-                format_label_line("buffer", &name)
+                fmt.resource("buffer", &name, &id);
             }
             Self::Draw { pipeline, .. } => {
                 if let Some(id) = pipeline {
                     let name = wgc::gfx_select!(id => global.render_pipeline_label(id));
-                    format_label_line("render pipeline", &name)
-                } else {
-                    String::new()
+                    fmt.resource("render pipeline", &name, &id);
                 }
             }
             Self::Dispatch { pipeline, .. } => {
                 if let Some(id) = pipeline {
                     let name = wgc::gfx_select!(id => global.compute_pipeline_label(id));
-                    format_label_line("compute pipeline", &name)
-                } else {
-                    String::new()
+                    fmt.resource("compute pipeline", &name, &id);
                 }
             }
-            _ => String::new(),
+            _ => {}
         }
     }
 }
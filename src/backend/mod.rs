@@ -1,7 +1,9 @@
 #[cfg(all(target_arch = "wasm32", not(feature = "webgl")))]
 mod web;
 #[cfg(all(target_arch = "wasm32", not(feature = "webgl")))]
-pub(crate) use web::{BufferMappedRange, Context};
+pub(crate) use web::{
+    error_kind, error_label, error_operation, error_report, BufferMappedRange, Context,
+};
 
 #[cfg(any(not(target_arch = "wasm32"), feature = "webgl"))]
 mod direct;
@@ -10,6 +12,8 @@ mod error;
 
 #[cfg(any(not(target_arch = "wasm32"), feature = "webgl"))]
 pub(crate) use direct::{BufferMappedRange, Context};
-
 #[cfg(any(not(target_arch = "wasm32"), feature = "webgl"))]
+pub(crate) use error::{error_kind, error_label, error_operation, error_report};
+
 mod native_gpu_future;
+pub(crate) use native_gpu_future::{new_gpu_future, GpuFutureCompletion};
@@ -16,8 +16,6 @@ pub struct GpuFuture<T> {
     data: Arc<GpuFutureData<T>>,
 }
 
-pub enum OpaqueData {}
-
 //TODO: merge this with `GpuFuture` and avoid `Arc` on the data.
 /// A completion handle to set the result on a GpuFuture
 pub struct GpuFutureCompletion<T> {
@@ -54,16 +52,6 @@ impl<T> GpuFutureCompletion<T> {
             }
         };
     }
-
-    pub(crate) fn into_raw(self) -> *mut OpaqueData {
-        Arc::into_raw(self.data) as _
-    }
-
-    pub(crate) unsafe fn from_raw(this: *mut OpaqueData) -> Self {
-        Self {
-            data: Arc::from_raw(this as _),
-        }
-    }
 }
 
 pub(crate) fn new_gpu_future<T>() -> (GpuFuture<T>, GpuFutureCompletion<T>) {
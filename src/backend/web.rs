@@ -30,6 +30,17 @@ impl fmt::Debug for Context {
     }
 }
 
+impl Context {
+    /// Creation sites are not tracked on the web.
+    #[cfg(feature = "track-caller")]
+    pub(crate) fn record_creation_site<T>(
+        &self,
+        _id: &T,
+        _location: &'static std::panic::Location<'static>,
+    ) {
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ComputePass(web_sys::GpuComputePassEncoder);
 #[derive(Debug)]
@@ -878,13 +889,85 @@ fn future_request_device(
             let queue_id = device_id.queue();
             (Sendable(device_id), Sendable(queue_id))
         })
-        .map_err(|_| crate::RequestDeviceError)
+        .map_err(|_| crate::RequestDeviceError::default())
 }
 
 fn future_map_async(result: JsFutureResult) -> Result<(), crate::BufferAsyncError> {
     result.map(|_| ()).map_err(|_| crate::BufferAsyncError)
 }
 
+fn future_pop_error_scope(
+    result: JsFutureResult,
+) -> Result<Option<crate::Error>, crate::PopErrorScopeError> {
+    match result {
+        Ok(js_value) if js_value.is_null() || js_value.is_undefined() => Ok(None),
+        Ok(js_value) => Ok(Some(
+            match js_value.dyn_into::<web_sys::GpuValidationError>() {
+                Ok(js_error) => crate::Error::ValidationError {
+                    description: js_error.message(),
+                    source: Box::new(WebError(js_error.message())),
+                },
+                Err(_) => crate::Error::OutOfMemoryError {
+                    source: Box::new(WebError("Out of Memory".to_string())),
+                },
+            },
+        )),
+        // The promise is rejected when there is no error scope to pop.
+        Err(_) => Err(crate::PopErrorScopeError),
+    }
+}
+
+fn future_device_lost(result: JsFutureResult) -> crate::DeviceLostInfo {
+    match result {
+        Ok(js_value) => {
+            let info = web_sys::GpuDeviceLostInfo::from(js_value);
+            crate::DeviceLostInfo {
+                reason: match info.reason() {
+                    Some(web_sys::GpuDeviceLostReason::Destroyed) => {
+                        crate::DeviceLostReason::Destroyed
+                    }
+                    _ => crate::DeviceLostReason::Unknown,
+                },
+                message: info.message(),
+            }
+        }
+        Err(_) => crate::DeviceLostInfo {
+            reason: crate::DeviceLostReason::Unknown,
+            message: String::new(),
+        },
+    }
+}
+
+/// An error reported by the browser's WebGPU implementation.
+#[derive(Debug)]
+struct WebError(String);
+
+impl fmt::Display for WebError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for WebError {}
+
+pub(crate) fn error_operation(_error: &(dyn std::error::Error + 'static)) -> Option<&str> {
+    None
+}
+
+pub(crate) fn error_label(_error: &(dyn std::error::Error + 'static)) -> Option<&str> {
+    None
+}
+
+pub(crate) fn error_report(
+    _error: &(dyn std::error::Error + 'static),
+) -> Option<&crate::ErrorReport> {
+    None
+}
+
+pub(crate) fn error_kind(_error: &(dyn std::error::Error + 'static)) -> crate::ErrorKind {
+    crate::ErrorKind::Other
+}
+
 impl crate::Context for Context {
     type AdapterId = Sendable<web_sys::GpuAdapter>;
     type DeviceId = Sendable<web_sys::GpuDevice>;
@@ -919,10 +1002,12 @@ impl crate::Context for Context {
         wasm_bindgen_futures::JsFuture,
         fn(JsFutureResult) -> Result<(Self::DeviceId, Self::QueueId), crate::RequestDeviceError>,
     >;
-    type MapAsyncFuture = MakeSendFuture<
+    type PopErrorScopeFuture = MakeSendFuture<
         wasm_bindgen_futures::JsFuture,
-        fn(JsFutureResult) -> Result<(), crate::BufferAsyncError>,
+        fn(JsFutureResult) -> Result<Option<crate::Error>, crate::PopErrorScopeError>,
     >;
+    type DeviceLostFuture =
+        MakeSendFuture<wasm_bindgen_futures::JsFuture, fn(JsFutureResult) -> crate::DeviceLostInfo>;
 
     fn init(_backends: wgt::BackendBit) -> Self {
         Context(web_sys::window().unwrap().navigator().gpu())
@@ -1042,10 +1127,10 @@ impl crate::Context for Context {
         &self,
         adapter: &Self::AdapterId,
         surface: &Self::SurfaceId,
-    ) -> Option<wgt::TextureFormat> {
+    ) -> Result<wgt::TextureFormat, crate::SwapChainPreferredFormatError> {
         let format =
             map_texture_format_from_web_sys(surface.0.get_swap_chain_preferred_format(&adapter.0));
-        Some(format)
+        Ok(format)
     }
 
     fn adapter_features(&self, adapter: &Self::AdapterId) -> wgt::Features {
@@ -1543,39 +1628,59 @@ impl crate::Context for Context {
         // TODO:
     }
 
-    fn buffer_map_async(
+    fn device_push_error_scope(&self, device: &Self::DeviceId, filter: crate::ErrorFilter) {
+        device.0.push_error_scope(match filter {
+            crate::ErrorFilter::OutOfMemory => web_sys::GpuErrorFilter::OutOfMemory,
+            crate::ErrorFilter::Validation => web_sys::GpuErrorFilter::Validation,
+        });
+    }
+
+    fn device_pop_error_scope(&self, device: &Self::DeviceId) -> Self::PopErrorScopeFuture {
+        let error_promise = device.0.pop_error_scope();
+        MakeSendFuture::new(
+            wasm_bindgen_futures::JsFuture::from(error_promise),
+            future_pop_error_scope,
+        )
+    }
+
+    fn device_lost(&self, device: &Self::DeviceId) -> Self::DeviceLostFuture {
+        MakeSendFuture::new(
+            wasm_bindgen_futures::JsFuture::from(device.0.lost()),
+            future_device_lost,
+        )
+    }
+
+    fn buffer_map_async_with(
         &self,
         buffer: &Self::BufferId,
         mode: crate::MapMode,
         range: Range<wgt::BufferAddress>,
-    ) -> Self::MapAsyncFuture {
+        callback: impl FnOnce(Result<(), crate::BufferAsyncError>) + Send + 'static,
+    ) {
         let map_promise = buffer.0.map_async_with_f64_and_f64(
             map_map_mode(mode),
             range.start as f64,
             (range.end - range.start) as f64,
         );
-
-        MakeSendFuture::new(
-            wasm_bindgen_futures::JsFuture::from(map_promise),
-            future_map_async,
-        )
+        let future = wasm_bindgen_futures::JsFuture::from(map_promise);
+        wasm_bindgen_futures::spawn_local(async move { callback(future_map_async(future.await)) });
     }
 
     fn buffer_get_mapped_range(
         &self,
         buffer: &Self::BufferId,
         sub_range: Range<wgt::BufferAddress>,
-    ) -> BufferMappedRange {
+    ) -> Result<BufferMappedRange, crate::MapError> {
         let array_buffer = buffer.0.get_mapped_range_with_f64_and_f64(
             sub_range.start as f64,
             (sub_range.end - sub_range.start) as f64,
         );
         let actual_mapping = js_sys::Uint8Array::new(&array_buffer);
         let temporary_mapping = actual_mapping.to_vec();
-        BufferMappedRange {
+        Ok(BufferMappedRange {
             actual_mapping,
             temporary_mapping,
-        }
+        })
     }
 
     fn buffer_unmap(&self, buffer: &Self::BufferId) {
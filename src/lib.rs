@@ -12,14 +12,16 @@ mod macros;
 
 use std::{
     borrow::Cow,
+    collections::VecDeque,
     error,
     fmt::{Debug, Display},
     future::Future,
     marker::PhantomData,
     num::{NonZeroU32, NonZeroU8},
     ops::{Bound, Range, RangeBounds},
-    sync::Arc,
-    thread,
+    pin::Pin,
+    sync::{Arc, Weak},
+    task, thread,
 };
 
 use parking_lot::Mutex;
@@ -181,7 +183,8 @@ trait Context: Debug + Send + Sized + Sync {
     type RequestAdapterFuture: Future<Output = Option<Self::AdapterId>> + Send;
     type RequestDeviceFuture: Future<Output = Result<(Self::DeviceId, Self::QueueId), RequestDeviceError>>
         + Send;
-    type MapAsyncFuture: Future<Output = Result<(), BufferAsyncError>> + Send;
+    type PopErrorScopeFuture: Future<Output = Result<Option<Error>, PopErrorScopeError>> + Send;
+    type DeviceLostFuture: Future<Output = DeviceLostInfo> + Send;
 
     fn init(backends: BackendBit) -> Self;
     fn instance_create_surface(
@@ -203,7 +206,7 @@ trait Context: Debug + Send + Sized + Sync {
         &self,
         adapter: &Self::AdapterId,
         surface: &Self::SurfaceId,
-    ) -> Option<TextureFormat>;
+    ) -> Result<TextureFormat, SwapChainPreferredFormatError>;
     fn adapter_features(&self, adapter: &Self::AdapterId) -> Features;
     fn adapter_limits(&self, adapter: &Self::AdapterId) -> Limits;
     fn adapter_downlevel_properties(&self, adapter: &Self::AdapterId) -> DownlevelProperties;
@@ -290,18 +293,22 @@ trait Context: Debug + Send + Sized + Sync {
         device: &Self::DeviceId,
         handler: impl UncapturedErrorHandler,
     );
+    fn device_push_error_scope(&self, device: &Self::DeviceId, filter: ErrorFilter);
+    fn device_pop_error_scope(&self, device: &Self::DeviceId) -> Self::PopErrorScopeFuture;
+    fn device_lost(&self, device: &Self::DeviceId) -> Self::DeviceLostFuture;
 
-    fn buffer_map_async(
+    fn buffer_map_async_with(
         &self,
         buffer: &Self::BufferId,
         mode: MapMode,
         range: Range<BufferAddress>,
-    ) -> Self::MapAsyncFuture;
+        callback: impl FnOnce(Result<(), BufferAsyncError>) + Send + 'static,
+    );
     fn buffer_get_mapped_range(
         &self,
         buffer: &Self::BufferId,
         sub_range: Range<BufferAddress>,
-    ) -> BufferMappedRange;
+    ) -> Result<BufferMappedRange, MapError>;
     fn buffer_unmap(&self, buffer: &Self::BufferId);
     fn swap_chain_get_current_texture_view(
         &self,
@@ -493,6 +500,8 @@ impl Drop for Adapter {
 pub struct Device {
     context: Arc<C>,
     id: <C as Context>::DeviceId,
+    /// Generator used by [`util::generate_mipmaps`], created on first use.
+    mipmap_generator: Mutex<Option<util::MipmapGenerator>>,
 }
 
 /// Passed to [`Device::poll`] to control if it should block or not. This has no effect on
@@ -510,7 +519,7 @@ pub enum Maintain {
 #[derive(Debug)]
 struct MapContext {
     total_size: BufferAddress,
-    initial_range: Range<BufferAddress>,
+    state: MapState,
     sub_ranges: Vec<Range<BufferAddress>>,
 }
 
@@ -518,41 +527,78 @@ impl MapContext {
     fn new(total_size: BufferAddress) -> Self {
         MapContext {
             total_size,
-            initial_range: 0..0,
+            state: MapState::Unmapped,
             sub_ranges: Vec::new(),
         }
     }
 
-    fn reset(&mut self) {
-        self.initial_range = 0..0;
+    fn state(&self) -> MapState {
+        self.state.clone()
+    }
 
-        assert!(
-            self.sub_ranges.is_empty(),
-            "You cannot unmap a buffer that still has accessible mapped views"
-        );
+    /// Resolves a pending mapping of `range`, unless the buffer was unmapped in the meantime.
+    fn resolve(&mut self, range: Range<BufferAddress>, success: bool) {
+        if self.state == MapState::Pending {
+            self.state = if success {
+                MapState::Mapped(range)
+            } else {
+                MapState::Unmapped
+            };
+        }
+    }
+
+    fn try_reset(&mut self) -> Result<(), MapError> {
+        if !self.sub_ranges.is_empty() {
+            return Err(MapError::ViewsAlive);
+        }
+        self.state = MapState::Unmapped;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.try_reset()
+            .unwrap_or_else(|err| panic!("You cannot unmap this buffer: {}", err));
     }
 
-    fn add(&mut self, offset: BufferAddress, size: Option<BufferSize>) -> BufferAddress {
+    fn try_add(
+        &mut self,
+        offset: BufferAddress,
+        size: Option<BufferSize>,
+    ) -> Result<BufferAddress, MapError> {
+        let mapped = match self.state {
+            MapState::Unmapped => return Err(MapError::NotMapped),
+            MapState::Pending => return Err(MapError::Pending),
+            MapState::Mapped(ref range) => range.clone(),
+        };
         let end = match size {
             Some(s) => offset + s.get(),
-            None => self.initial_range.end,
+            None => mapped.end,
         };
-        assert!(self.initial_range.start <= offset && end <= self.initial_range.end);
-        for sub in self.sub_ranges.iter() {
-            assert!(
-                end <= sub.start || offset >= sub.end,
-                "Intersecting map range with {:?}",
-                sub
-            );
+        if offset < mapped.start || end > mapped.end {
+            return Err(MapError::OutOfRange {
+                range: offset..end,
+                mapped,
+            });
+        }
+        if let Some(sub) = self
+            .sub_ranges
+            .iter()
+            .find(|sub| end > sub.start && offset < sub.end)
+        {
+            return Err(MapError::Overlapping {
+                range: offset..end,
+                existing: sub.clone(),
+            });
         }
         self.sub_ranges.push(offset..end);
-        end
+        Ok(end)
     }
 
     fn remove(&mut self, offset: BufferAddress, size: Option<BufferSize>) {
-        let end = match size {
-            Some(s) => offset + s.get(),
-            None => self.initial_range.end,
+        let end = match (size, &self.state) {
+            (Some(s), _) => offset + s.get(),
+            (None, MapState::Mapped(range)) => range.end,
+            (None, _) => panic!("unable to remove range from map context"),
         };
 
         let index = self
@@ -571,7 +617,8 @@ impl MapContext {
 pub struct Buffer {
     context: Arc<C>,
     id: <C as Context>::BufferId,
-    map_context: Mutex<MapContext>,
+    map_context: Arc<Mutex<MapContext>>,
+    size: BufferAddress,
     usage: BufferUsage,
 }
 
@@ -594,6 +641,12 @@ pub struct BufferSlice<'a> {
 pub struct Texture {
     context: Arc<C>,
     id: <C as Context>::TextureId,
+    size: Extent3d,
+    format: TextureFormat,
+    mip_level_count: u32,
+    sample_count: u32,
+    dimension: TextureDimension,
+    usage: TextureUsage,
     owned: bool,
 }
 
@@ -605,6 +658,11 @@ pub struct Texture {
 pub struct TextureView {
     context: Arc<C>,
     id: <C as Context>::TextureViewId,
+    format: TextureFormat,
+    dimension: TextureViewDimension,
+    aspect: TextureAspect,
+    mip_levels: Range<u32>,
+    array_layers: Range<u32>,
     owned: bool,
 }
 
@@ -617,6 +675,69 @@ pub struct TextureView {
 pub struct Sampler {
     context: Arc<C>,
     id: <C as Context>::SamplerId,
+    descriptor: SamplerDescriptor<'static>,
+}
+
+impl Sampler {
+    /// Returns the descriptor the sampler was created with, without its label.
+    pub fn descriptor(&self) -> &SamplerDescriptor<'static> {
+        &self.descriptor
+    }
+
+    /// Returns how out of bounds accesses are handled in the u (i.e. x) direction.
+    pub fn address_mode_u(&self) -> AddressMode {
+        self.descriptor.address_mode_u
+    }
+
+    /// Returns how out of bounds accesses are handled in the v (i.e. y) direction.
+    pub fn address_mode_v(&self) -> AddressMode {
+        self.descriptor.address_mode_v
+    }
+
+    /// Returns how out of bounds accesses are handled in the w (i.e. z) direction.
+    pub fn address_mode_w(&self) -> AddressMode {
+        self.descriptor.address_mode_w
+    }
+
+    /// Returns how the texture is filtered when it's magnified.
+    pub fn mag_filter(&self) -> FilterMode {
+        self.descriptor.mag_filter
+    }
+
+    /// Returns how the texture is filtered when it's minified.
+    pub fn min_filter(&self) -> FilterMode {
+        self.descriptor.min_filter
+    }
+
+    /// Returns how the texture is filtered between mip levels.
+    pub fn mipmap_filter(&self) -> FilterMode {
+        self.descriptor.mipmap_filter
+    }
+
+    /// Returns the minimum level of detail used.
+    pub fn lod_min_clamp(&self) -> f32 {
+        self.descriptor.lod_min_clamp
+    }
+
+    /// Returns the maximum level of detail used.
+    pub fn lod_max_clamp(&self) -> f32 {
+        self.descriptor.lod_max_clamp
+    }
+
+    /// Returns the comparison function of a comparison sampler.
+    pub fn compare(&self) -> Option<CompareFunction> {
+        self.descriptor.compare
+    }
+
+    /// Returns the maximum anisotropy used.
+    pub fn anisotropy_clamp(&self) -> Option<NonZeroU8> {
+        self.descriptor.anisotropy_clamp
+    }
+
+    /// Returns the border color used with [`AddressMode::ClampToBorder`].
+    pub fn border_color(&self) -> Option<SamplerBorderColor> {
+        self.descriptor.border_color
+    }
 }
 
 impl Drop for Sampler {
@@ -653,6 +774,7 @@ impl Drop for Surface {
 pub struct SwapChain {
     context: Arc<C>,
     id: <C as Context>::SwapChainId,
+    format: TextureFormat,
 }
 
 /// Handle to a binding group layout.
@@ -920,6 +1042,20 @@ impl Drop for RenderBundle {
 pub struct QuerySet {
     context: Arc<C>,
     id: <C as Context>::QuerySetId,
+    ty: QueryType,
+    count: u32,
+}
+
+impl QuerySet {
+    /// Returns the type of the queries in the set.
+    pub fn ty(&self) -> QueryType {
+        self.ty
+    }
+
+    /// Returns the number of queries in the set.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
 }
 
 impl Drop for QuerySet {
@@ -1438,12 +1574,16 @@ impl Adapter {
     /// - `trace_path` - Can be used for API call tracing, if that feature is
     ///   enabled in `wgpu-core`.
     ///
-    /// # Panics
+    /// # Errors
+    ///
+    /// Returns [`RequestDeviceError`] if:
     ///
     /// - Features specified by `desc` are not supported by this adapter.
     /// - Unsafe features were requested but not enabled when requesting the adapter.
     /// - Limits requested exceed the values provided by the adapter.
     /// - Adapter does not support all features wgpu requires to safely operate.
+    ///
+    /// The cause is logged as an error, and kept as the source of the returned error.
     pub fn request_device(
         &self,
         desc: &DeviceDescriptor,
@@ -1457,6 +1597,7 @@ impl Adapter {
                     Device {
                         context: Arc::clone(&context),
                         id: device_id,
+                        mipmap_generator: Mutex::new(None),
                     },
                     Queue {
                         context,
@@ -1469,8 +1610,14 @@ impl Adapter {
 
     /// Returns an optimal texture format to use for the [`SwapChain`] with this adapter.
     ///
-    /// Returns None if the surface is incompatible with the adapter.
-    pub fn get_swap_chain_preferred_format(&self, surface: &Surface) -> Option<TextureFormat> {
+    /// # Errors
+    ///
+    /// Returns [`SwapChainPreferredFormatError::Incompatible`] if the surface is
+    /// incompatible with the adapter.
+    pub fn get_swap_chain_preferred_format(
+        &self,
+        surface: &Surface,
+    ) -> Result<TextureFormat, SwapChainPreferredFormatError> {
         Context::adapter_get_swap_chain_preferred_format(&*self.context, &self.id, &surface.id)
     }
 
@@ -1530,24 +1677,16 @@ impl Device {
     }
 
     /// Creates a shader module from either SPIR-V or WGSL source code.
+    #[cfg_attr(feature = "track-caller", track_caller)]
     pub fn create_shader_module(&self, desc: &ShaderModuleDescriptor) -> ShaderModule {
-        ShaderModule {
-            context: Arc::clone(&self.context),
-            id: Context::device_create_shader_module(&*self.context, &self.id, desc),
-        }
+        let id = Context::device_create_shader_module(&*self.context, &self.id, desc);
+        self.shader_module_from_id(id)
     }
 
     /// Creates an empty [`CommandEncoder`].
     pub fn create_command_encoder(&self, desc: &CommandEncoderDescriptor) -> CommandEncoder {
-        CommandEncoder {
-            context: Arc::clone(&self.context),
-            id: Some(Context::device_create_command_encoder(
-                &*self.context,
-                &self.id,
-                desc,
-            )),
-            _p: Default::default(),
-        }
+        let id = Context::device_create_command_encoder(&*self.context, &self.id, desc);
+        self.command_encoder_from_id(id)
     }
 
     /// Creates an empty [`RenderBundleEncoder`].
@@ -1564,85 +1703,384 @@ impl Device {
     }
 
     /// Creates a new [`BindGroup`].
+    #[cfg_attr(feature = "track-caller", track_caller)]
     pub fn create_bind_group(&self, desc: &BindGroupDescriptor) -> BindGroup {
+        let id = Context::device_create_bind_group(&*self.context, &self.id, desc);
+        self.bind_group_from_id(id)
+    }
+
+    /// Creates a [`BindGroupLayout`].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_bind_group_layout(&self, desc: &BindGroupLayoutDescriptor) -> BindGroupLayout {
+        let id = Context::device_create_bind_group_layout(&*self.context, &self.id, desc);
+        self.bind_group_layout_from_id(id)
+    }
+
+    /// Creates a [`PipelineLayout`].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_pipeline_layout(&self, desc: &PipelineLayoutDescriptor) -> PipelineLayout {
+        let id = Context::device_create_pipeline_layout(&*self.context, &self.id, desc);
+        self.pipeline_layout_from_id(id)
+    }
+
+    /// Creates a [`RenderPipeline`].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_render_pipeline(&self, desc: &RenderPipelineDescriptor) -> RenderPipeline {
+        let id = Context::device_create_render_pipeline(&*self.context, &self.id, desc);
+        self.render_pipeline_from_id(id)
+    }
+
+    /// Creates a [`ComputePipeline`].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_compute_pipeline(&self, desc: &ComputePipelineDescriptor) -> ComputePipeline {
+        let id = Context::device_create_compute_pipeline(&*self.context, &self.id, desc);
+        self.compute_pipeline_from_id(id)
+    }
+
+    /// Creates a [`Buffer`].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_buffer(&self, desc: &BufferDescriptor) -> Buffer {
+        let id = Context::device_create_buffer(&*self.context, &self.id, desc);
+        self.buffer_from_id(id, desc)
+    }
+
+    /// Creates a new [`Texture`].
+    ///
+    /// `desc` specifies the general format of the texture.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_texture(&self, desc: &TextureDescriptor) -> Texture {
+        let id = Context::device_create_texture(&*self.context, &self.id, desc);
+        self.texture_from_id(id, desc)
+    }
+
+    /// Creates a new [`Sampler`].
+    ///
+    /// `desc` specifies the behavior of the sampler.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_sampler(&self, desc: &SamplerDescriptor) -> Sampler {
+        let id = Context::device_create_sampler(&*self.context, &self.id, desc);
+        self.sampler_from_id(id, desc)
+    }
+
+    /// Creates a new [`QuerySet`].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_query_set(&self, desc: &QuerySetDescriptor) -> QuerySet {
+        let id = Context::device_create_query_set(&*self.context, &self.id, desc);
+        self.query_set_from_id(id, desc)
+    }
+
+    /// Creates a [`CommandEncoder`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn try_create_command_encoder(
+        &self,
+        desc: &CommandEncoderDescriptor,
+    ) -> Result<CommandEncoder, Error> {
+        let (id, error) = self
+            .context
+            .device_create_command_encoder_with_error(&self.id, desc);
+        let value = self.command_encoder_from_id(id);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`QuerySet`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_query_set(&self, desc: &QuerySetDescriptor) -> Result<QuerySet, Error> {
+        let (id, error) = self
+            .context
+            .device_create_query_set_with_error(&self.id, desc);
+        let value = self.query_set_from_id(id, desc);
+        self.try_result(value, error)
+    }
+
+    /// Creates a shader module, returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_shader_module(
+        &self,
+        desc: &ShaderModuleDescriptor,
+    ) -> Result<ShaderModule, Error> {
+        let (id, error) = self
+            .context
+            .device_create_shader_module_with_error(&self.id, desc);
+        let value = self.shader_module_from_id(id);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`BindGroup`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_bind_group(&self, desc: &BindGroupDescriptor) -> Result<BindGroup, Error> {
+        let (id, error) = self
+            .context
+            .device_create_bind_group_with_error(&self.id, desc);
+        let value = self.bind_group_from_id(id);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`BindGroupLayout`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_bind_group_layout(
+        &self,
+        desc: &BindGroupLayoutDescriptor,
+    ) -> Result<BindGroupLayout, Error> {
+        let (id, error) = self
+            .context
+            .device_create_bind_group_layout_with_error(&self.id, desc);
+        let value = self.bind_group_layout_from_id(id);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`PipelineLayout`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_pipeline_layout(
+        &self,
+        desc: &PipelineLayoutDescriptor,
+    ) -> Result<PipelineLayout, Error> {
+        let (id, error) = self
+            .context
+            .device_create_pipeline_layout_with_error(&self.id, desc);
+        let value = self.pipeline_layout_from_id(id);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`RenderPipeline`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_render_pipeline(
+        &self,
+        desc: &RenderPipelineDescriptor,
+    ) -> Result<RenderPipeline, Error> {
+        let (id, error) = self
+            .context
+            .device_create_render_pipeline_with_error(&self.id, desc);
+        let value = self.render_pipeline_from_id(id);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`ComputePipeline`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_compute_pipeline(
+        &self,
+        desc: &ComputePipelineDescriptor,
+    ) -> Result<ComputePipeline, Error> {
+        let (id, error) = self
+            .context
+            .device_create_compute_pipeline_with_error(&self.id, desc);
+        let value = self.compute_pipeline_from_id(id);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`Buffer`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_buffer(&self, desc: &BufferDescriptor) -> Result<Buffer, Error> {
+        let (id, error) = self.context.device_create_buffer_with_error(&self.id, desc);
+        let value = self.buffer_from_id(id, desc);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`Texture`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_texture(&self, desc: &TextureDescriptor) -> Result<Texture, Error> {
+        let (id, error) = self
+            .context
+            .device_create_texture_with_error(&self.id, desc);
+        let value = self.texture_from_id(id, desc);
+        self.try_result(value, error)
+    }
+
+    /// Creates a [`Sampler`], returning the error instead of passing it
+    /// to the uncaptured error handler.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_create_sampler(&self, desc: &SamplerDescriptor) -> Result<Sampler, Error> {
+        let (id, error) = self
+            .context
+            .device_create_sampler_with_error(&self.id, desc);
+        let value = self.sampler_from_id(id, desc);
+        self.try_result(value, error)
+    }
+
+    /// Returns `value`, or `error` if creating it failed.
+    ///
+    /// The error isn't reported, but the loss of the device is still signaled
+    /// when it caused the error.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn try_result<T>(&self, value: T, error: Option<Error>) -> Result<T, Error> {
+        match error {
+            Some(err) => {
+                self.context.device_check_lost(&self.id, &err);
+                // On error, the invalid id is released when the value is dropped
+                Err(err)
+            }
+            None => Ok(value),
+        }
+    }
+
+    fn command_encoder_from_id(&self, id: <C as Context>::CommandEncoderId) -> CommandEncoder {
+        CommandEncoder {
+            context: Arc::clone(&self.context),
+            id: Some(id),
+            _p: Default::default(),
+        }
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn query_set_from_id(
+        &self,
+        id: <C as Context>::QuerySetId,
+        desc: &QuerySetDescriptor,
+    ) -> QuerySet {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
+        QuerySet {
+            context: Arc::clone(&self.context),
+            id,
+            ty: desc.ty,
+            count: desc.count,
+        }
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn shader_module_from_id(&self, id: <C as Context>::ShaderModuleId) -> ShaderModule {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
+        ShaderModule {
+            context: Arc::clone(&self.context),
+            id,
+        }
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn bind_group_from_id(&self, id: <C as Context>::BindGroupId) -> BindGroup {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
         BindGroup {
             context: Arc::clone(&self.context),
-            id: Context::device_create_bind_group(&*self.context, &self.id, desc),
+            id,
         }
     }
 
-    /// Creates a [`BindGroupLayout`].
-    pub fn create_bind_group_layout(&self, desc: &BindGroupLayoutDescriptor) -> BindGroupLayout {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn bind_group_layout_from_id(&self, id: <C as Context>::BindGroupLayoutId) -> BindGroupLayout {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
         BindGroupLayout {
             context: Arc::clone(&self.context),
-            id: Context::device_create_bind_group_layout(&*self.context, &self.id, desc),
+            id,
         }
     }
 
-    /// Creates a [`PipelineLayout`].
-    pub fn create_pipeline_layout(&self, desc: &PipelineLayoutDescriptor) -> PipelineLayout {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn pipeline_layout_from_id(&self, id: <C as Context>::PipelineLayoutId) -> PipelineLayout {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
         PipelineLayout {
             context: Arc::clone(&self.context),
-            id: Context::device_create_pipeline_layout(&*self.context, &self.id, desc),
+            id,
         }
     }
 
-    /// Creates a [`RenderPipeline`].
-    pub fn create_render_pipeline(&self, desc: &RenderPipelineDescriptor) -> RenderPipeline {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn render_pipeline_from_id(&self, id: <C as Context>::RenderPipelineId) -> RenderPipeline {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
         RenderPipeline {
             context: Arc::clone(&self.context),
-            id: Context::device_create_render_pipeline(&*self.context, &self.id, desc),
+            id,
         }
     }
 
-    /// Creates a [`ComputePipeline`].
-    pub fn create_compute_pipeline(&self, desc: &ComputePipelineDescriptor) -> ComputePipeline {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn compute_pipeline_from_id(&self, id: <C as Context>::ComputePipelineId) -> ComputePipeline {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
         ComputePipeline {
             context: Arc::clone(&self.context),
-            id: Context::device_create_compute_pipeline(&*self.context, &self.id, desc),
+            id,
         }
     }
 
-    /// Creates a [`Buffer`].
-    pub fn create_buffer(&self, desc: &BufferDescriptor) -> Buffer {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn buffer_from_id(&self, id: <C as Context>::BufferId, desc: &BufferDescriptor) -> Buffer {
         let mut map_context = MapContext::new(desc.size);
         if desc.mapped_at_creation {
-            map_context.initial_range = 0..desc.size;
+            map_context.state = MapState::Mapped(0..desc.size);
         }
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
         Buffer {
             context: Arc::clone(&self.context),
-            id: Context::device_create_buffer(&*self.context, &self.id, desc),
-            map_context: Mutex::new(map_context),
+            id,
+            map_context: Arc::new(Mutex::new(map_context)),
+            size: desc.size,
             usage: desc.usage,
         }
     }
 
-    /// Creates a new [`Texture`].
-    ///
-    /// `desc` specifies the general format of the texture.
-    pub fn create_texture(&self, desc: &TextureDescriptor) -> Texture {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn texture_from_id(&self, id: <C as Context>::TextureId, desc: &TextureDescriptor) -> Texture {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
         Texture {
             context: Arc::clone(&self.context),
-            id: Context::device_create_texture(&*self.context, &self.id, desc),
+            id,
+            size: desc.size,
+            format: desc.format,
+            mip_level_count: desc.mip_level_count,
+            sample_count: desc.sample_count,
+            dimension: desc.dimension,
+            usage: desc.usage,
             owned: true,
         }
     }
 
-    /// Creates a new [`Sampler`].
-    ///
-    /// `desc` specifies the behavior of the sampler.
-    pub fn create_sampler(&self, desc: &SamplerDescriptor) -> Sampler {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn sampler_from_id(&self, id: <C as Context>::SamplerId, desc: &SamplerDescriptor) -> Sampler {
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
         Sampler {
             context: Arc::clone(&self.context),
-            id: Context::device_create_sampler(&*self.context, &self.id, desc),
-        }
-    }
-
-    /// Creates a new [`QuerySet`].
-    pub fn create_query_set(&self, desc: &QuerySetDescriptor) -> QuerySet {
-        QuerySet {
-            context: Arc::clone(&self.context),
-            id: Context::device_create_query_set(&*self.context, &self.id, desc),
+            id,
+            descriptor: SamplerDescriptor {
+                label: None,
+                address_mode_u: desc.address_mode_u,
+                address_mode_v: desc.address_mode_v,
+                address_mode_w: desc.address_mode_w,
+                mag_filter: desc.mag_filter,
+                min_filter: desc.min_filter,
+                mipmap_filter: desc.mipmap_filter,
+                lod_min_clamp: desc.lod_min_clamp,
+                lod_max_clamp: desc.lod_max_clamp,
+                compare: desc.compare,
+                anisotropy_clamp: desc.anisotropy_clamp,
+                border_color: desc.border_color,
+            },
         }
     }
 
@@ -1656,6 +2094,7 @@ impl Device {
         SwapChain {
             context: Arc::clone(&self.context),
             id: Context::device_create_swap_chain(&*self.context, &self.id, &surface.id, desc),
+            format: desc.format,
         }
     }
 
@@ -1664,6 +2103,57 @@ impl Device {
         self.context.device_on_uncaptured_error(&self.id, handler);
     }
 
+    /// Returns a stream of the errors that are not handled in error scopes.
+    ///
+    /// This replaces the uncaptured error handler, like [`Device::on_uncaptured_error`].
+    /// The stream ends when the handler is replaced again. Otherwise, the handler is shared
+    /// with the buffers, textures, command encoders and swap chains of the device, so the
+    /// stream only ends once the device and all of these are dropped.
+    /// Errors raised after the stream is dropped are logged.
+    ///
+    /// On the web, uncaptured error handlers are not supported yet, and the stream
+    /// ends immediately.
+    pub fn uncaptured_errors(&self) -> UncapturedErrors {
+        let (errors, handler) = UncapturedErrors::new();
+        self.on_uncaptured_error(handler);
+        errors
+    }
+
+    /// Push an error scope.
+    ///
+    /// Until the scope is popped, errors matching `filter` are captured by this scope
+    /// instead of being passed to the uncaptured error handler.
+    /// Only the first captured error is kept.
+    pub fn push_error_scope(&self, filter: ErrorFilter) {
+        self.context.device_push_error_scope(&self.id, filter);
+    }
+
+    /// Pop an error scope.
+    ///
+    /// Resolves to the first error captured by the scope, if any.
+    ///
+    /// # Errors
+    ///
+    /// Resolves to [`PopErrorScopeError`] if there is no error scope to pop.
+    pub fn pop_error_scope(
+        &self,
+    ) -> impl Future<Output = Result<Option<Error>, PopErrorScopeError>> + Send {
+        self.context.device_pop_error_scope(&self.id)
+    }
+
+    /// Returns a future that resolves once the device is lost.
+    ///
+    /// A lost device can't be recovered: every operation on it, or on the resources created
+    /// from it, becomes a no-op. To recover, drop the device, its queue and all the resources
+    /// created from them, request a new device from the [`Adapter`] (or from a new adapter if
+    /// the old one is gone as well), and re-create the resources from their descriptors.
+    ///
+    /// On native, the device is reported lost when the backend reports it, typically
+    /// while calling [`Device::poll`].
+    pub fn lost(&self) -> impl Future<Output = DeviceLostInfo> + Send {
+        self.context.device_lost(&self.id)
+    }
+
     /// Starts frame capture.
     pub fn start_capture(&self) {
         Context::device_start_capture(&*self.context, &self.id)
@@ -1683,9 +2173,40 @@ impl Drop for Device {
     }
 }
 
+/// Reason for a device to be lost.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum DeviceLostReason {
+    /// The device was lost for an unknown reason, e.g. a driver reset.
+    Unknown,
+    /// The device was destroyed on purpose.
+    Destroyed,
+}
+
+/// Information about a lost device, returned by [`Device::lost`].
+#[derive(Clone, Debug)]
+pub struct DeviceLostInfo {
+    /// Why the device was lost.
+    pub reason: DeviceLostReason,
+    /// Message provided by the implementation. Only meant for debugging.
+    pub message: String,
+}
+
 /// Requesting a device failed.
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct RequestDeviceError;
+///
+/// The cause, when known, is available through [`std::error::Error::source`].
+#[derive(Clone, Debug, Default)]
+pub struct RequestDeviceError {
+    source: Option<Arc<dyn error::Error + Send + Sync + 'static>>,
+}
+
+impl RequestDeviceError {
+    #[cfg(any(not(target_arch = "wasm32"), feature = "webgl"))]
+    fn new(source: impl error::Error + Send + Sync + 'static) -> Self {
+        RequestDeviceError {
+            source: Some(Arc::new(source)),
+        }
+    }
+}
 
 impl Display for RequestDeviceError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -1693,23 +2214,194 @@ impl Display for RequestDeviceError {
     }
 }
 
-impl error::Error for RequestDeviceError {}
+// Errors are equal when they have the same cause, compared by description, as
+// the causes themselves can't be compared.
+impl PartialEq for RequestDeviceError {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.source, &other.source) {
+            (None, None) => true,
+            (Some(source), Some(other_source)) => source.to_string() == other_source.to_string(),
+            _ => false,
+        }
+    }
+}
 
-/// Error occurred when trying to async map a buffer.
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct BufferAsyncError;
+impl Eq for RequestDeviceError {}
 
-impl Display for BufferAsyncError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Error occurred when trying to async map a buffer")
+impl error::Error for RequestDeviceError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| source.as_ref() as &(dyn error::Error + 'static))
     }
 }
 
-impl error::Error for BufferAsyncError {}
+/// Getting the preferred format of a swap chain failed.
+#[derive(Clone, Debug)]
+pub enum SwapChainPreferredFormatError {
+    /// The surface is incompatible with the adapter.
+    Incompatible,
+    /// The backend failed to query the format.
+    Backend(Arc<dyn error::Error + Send + Sync + 'static>),
+}
 
-/// Type of buffer mapping.
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum MapMode {
+impl Display for SwapChainPreferredFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            SwapChainPreferredFormatError::Incompatible => {
+                write!(f, "Surface is incompatible with the adapter")
+            }
+            SwapChainPreferredFormatError::Backend(ref source) => {
+                write!(f, "Getting the preferred format failed: {}", source)
+            }
+        }
+    }
+}
+
+impl error::Error for SwapChainPreferredFormatError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SwapChainPreferredFormatError::Incompatible => None,
+            SwapChainPreferredFormatError::Backend(source) => {
+                Some(source.as_ref() as &(dyn error::Error + 'static))
+            }
+        }
+    }
+}
+
+/// Error returned by [`Device::pop_error_scope`] when there is no error scope to pop.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PopErrorScopeError;
+
+impl Display for PopErrorScopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "There is no error scope to pop")
+    }
+}
+
+impl error::Error for PopErrorScopeError {}
+
+/// Error occurred when trying to async map a buffer.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BufferAsyncError;
+
+impl Display for BufferAsyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Error occurred when trying to async map a buffer")
+    }
+}
+
+impl error::Error for BufferAsyncError {}
+
+/// State of the host mapping of a [`Buffer`], as returned by [`Buffer::map_state`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapState {
+    /// The buffer is not mapped.
+    Unmapped,
+    /// A mapping was requested with [`BufferSlice::map_async`], but is not resolved yet.
+    Pending,
+    /// The given range of the buffer is mapped and can be accessed.
+    Mapped(Range<BufferAddress>),
+}
+
+/// Error occurred when trying to access the mapping of a buffer.
+#[derive(Debug)]
+pub enum MapError {
+    /// The buffer is not mapped.
+    NotMapped,
+    /// The mapping of the buffer is not resolved yet.
+    Pending,
+    /// The requested range is not within the mapped range.
+    OutOfRange {
+        /// Requested range.
+        range: Range<BufferAddress>,
+        /// Range of the buffer that is mapped.
+        mapped: Range<BufferAddress>,
+    },
+    /// The requested range intersects a view that is still alive.
+    Overlapping {
+        /// Requested range.
+        range: Range<BufferAddress>,
+        /// Range of the existing view.
+        existing: Range<BufferAddress>,
+    },
+    /// Views of the mapping are still alive.
+    ViewsAlive,
+    /// The backend refused to provide the mapped range.
+    Backend(Error),
+}
+
+impl PartialEq for MapError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (MapError::NotMapped, MapError::NotMapped) => true,
+            (MapError::Pending, MapError::Pending) => true,
+            (
+                MapError::OutOfRange { range, mapped },
+                MapError::OutOfRange {
+                    range: other_range,
+                    mapped: other_mapped,
+                },
+            ) => range == other_range && mapped == other_mapped,
+            (
+                MapError::Overlapping { range, existing },
+                MapError::Overlapping {
+                    range: other_range,
+                    existing: other_existing,
+                },
+            ) => range == other_range && existing == other_existing,
+            (MapError::ViewsAlive, MapError::ViewsAlive) => true,
+            // Backend errors are compared by their description, as their causes
+            // can't be compared.
+            (MapError::Backend(error), MapError::Backend(other_error)) => {
+                error.to_string() == other_error.to_string()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for MapError {}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            MapError::NotMapped => write!(f, "Buffer is not mapped"),
+            MapError::Pending => write!(f, "Buffer mapping is not resolved yet"),
+            MapError::OutOfRange {
+                ref range,
+                ref mapped,
+            } => write!(
+                f,
+                "Range {:?} is outside of the mapped range {:?}",
+                range, mapped
+            ),
+            MapError::Overlapping {
+                ref range,
+                ref existing,
+            } => write!(
+                f,
+                "Range {:?} intersects the mapped view {:?}",
+                range, existing
+            ),
+            MapError::ViewsAlive => write!(f, "Mapped views of the buffer are still alive"),
+            MapError::Backend(ref error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl error::Error for MapError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            MapError::Backend(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Type of buffer mapping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MapMode {
     /// Map only for reading
     Read,
     /// Map only for writing
@@ -1736,7 +2428,20 @@ fn range_to_offset_size<S: RangeBounds<BufferAddress>>(
 
 #[cfg(test)]
 mod tests {
-    use crate::BufferSize;
+    use crate::{
+        cast_mapping, BufferSize, Error, MapContext, MapError, MapState, UncapturedErrors,
+    };
+    use bytemuck::PodCastError;
+    use futures_core::Stream;
+    use std::{
+        io,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task,
+    };
 
     #[test]
     fn range_to_offset_size_works() {
@@ -1759,6 +2464,212 @@ mod tests {
     fn range_to_offset_size_panics_for_unbounded_empty_range() {
         crate::range_to_offset_size(..0);
     }
+
+    #[test]
+    fn map_context_state() {
+        let mut mc = MapContext::new(16);
+        assert_eq!(mc.state(), MapState::Unmapped);
+        mc.state = MapState::Pending;
+        assert_eq!(mc.state(), MapState::Pending);
+        mc.resolve(4..12, true);
+        assert_eq!(mc.state(), MapState::Mapped(4..12));
+        assert_eq!(mc.try_reset(), Ok(()));
+        assert_eq!(mc.state(), MapState::Unmapped);
+
+        // Unmapped while pending
+        mc.state = MapState::Pending;
+        assert_eq!(mc.try_reset(), Ok(()));
+        mc.resolve(4..12, true);
+        assert_eq!(mc.state(), MapState::Unmapped);
+
+        mc.state = MapState::Pending;
+        mc.resolve(4..12, false);
+        assert_eq!(mc.state(), MapState::Unmapped);
+    }
+
+    #[test]
+    fn map_context_zero_size_mapped_at_creation() {
+        let mut mc = MapContext::new(0);
+        mc.state = MapState::Mapped(0..0);
+        assert_eq!(mc.state(), MapState::Mapped(0..0));
+        assert_eq!(mc.try_add(0, None), Ok(0));
+        mc.remove(0, None);
+        assert_eq!(mc.try_reset(), Ok(()));
+        assert_eq!(mc.state(), MapState::Unmapped);
+    }
+
+    #[test]
+    fn map_context_try_add() {
+        let mut mc = MapContext::new(16);
+        assert_eq!(mc.try_add(0, None), Err(MapError::NotMapped));
+        mc.state = MapState::Pending;
+        assert_eq!(mc.try_add(0, None), Err(MapError::Pending));
+        mc.resolve(4..12, true);
+
+        assert_eq!(
+            mc.try_add(0, BufferSize::new(4)),
+            Err(MapError::OutOfRange {
+                range: 0..4,
+                mapped: 4..12,
+            })
+        );
+        assert_eq!(mc.try_add(4, BufferSize::new(4)), Ok(8));
+        assert_eq!(
+            mc.try_add(6, BufferSize::new(4)),
+            Err(MapError::Overlapping {
+                range: 6..10,
+                existing: 4..8,
+            })
+        );
+        assert_eq!(mc.try_add(8, None), Ok(12));
+        assert_eq!(mc.try_reset(), Err(MapError::ViewsAlive));
+
+        mc.remove(4, BufferSize::new(4));
+        mc.remove(8, None);
+        assert_eq!(mc.try_reset(), Ok(()));
+    }
+
+    #[test]
+    fn cast_mapping_works() {
+        // Backed by u32s so that the start is aligned for them
+        let words = [0x0403_0201u32, 0x0807_0605, 0x0c0b_0a09];
+        let bytes: &[u8] = bytemuck::cast_slice(&words);
+
+        assert_eq!(cast_mapping::<u32>(bytes), Ok(&words[..]));
+        assert_eq!(cast_mapping::<u16>(&bytes[2..6]).map(<[u16]>::len), Ok(2));
+        assert_eq!(
+            cast_mapping::<u32>(&bytes[..6]),
+            Err(PodCastError::OutputSliceWouldHaveSlop)
+        );
+        assert_eq!(
+            cast_mapping::<u32>(&bytes[2..10]),
+            Err(PodCastError::TargetAlignmentGreaterAndInputNotAligned)
+        );
+    }
+
+    /// Returns a waker counting how many times it's woken.
+    fn counting_waker(wakes: &Arc<AtomicUsize>) -> task::Waker {
+        const VTABLE: task::RawWakerVTable =
+            task::RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+        unsafe fn clone(data: *const ()) -> task::RawWaker {
+            let wakes = Arc::from_raw(data as *const AtomicUsize);
+            let cloned = Arc::clone(&wakes);
+            std::mem::forget(wakes);
+            task::RawWaker::new(Arc::into_raw(cloned) as *const (), &VTABLE)
+        }
+        unsafe fn wake(data: *const ()) {
+            Arc::from_raw(data as *const AtomicUsize).fetch_add(1, Ordering::SeqCst);
+        }
+        unsafe fn wake_by_ref(data: *const ()) {
+            (*(data as *const AtomicUsize)).fetch_add(1, Ordering::SeqCst);
+        }
+        unsafe fn drop(data: *const ()) {
+            std::mem::drop(Arc::from_raw(data as *const AtomicUsize));
+        }
+
+        let data = Arc::into_raw(Arc::clone(wakes)) as *const ();
+        unsafe { task::Waker::from_raw(task::RawWaker::new(data, &VTABLE)) }
+    }
+
+    fn error(description: &str) -> Error {
+        Error::ValidationError {
+            description: description.to_string(),
+            source: Box::new(io::Error::new(io::ErrorKind::Other, description)),
+        }
+    }
+
+    /// Polls `errors` once, returning the description of the error it yields.
+    fn poll_description(
+        errors: &mut UncapturedErrors,
+        cx: &mut task::Context<'_>,
+    ) -> task::Poll<Option<String>> {
+        Pin::new(errors).poll_next(cx).map(|item| {
+            item.map(|error| match error {
+                Error::ValidationError { description, .. } => description,
+                Error::OutOfMemoryError { .. } => unreachable!(),
+            })
+        })
+    }
+
+    #[test]
+    fn uncaptured_errors_wake() {
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let waker = counting_waker(&wakes);
+        let mut cx = task::Context::from_waker(&waker);
+        let (mut errors, handler) = UncapturedErrors::new();
+
+        assert_eq!(poll_description(&mut errors, &mut cx), task::Poll::Pending);
+        assert_eq!(wakes.load(Ordering::SeqCst), 0);
+
+        handler(error("first"));
+        assert_eq!(wakes.load(Ordering::SeqCst), 1);
+        // The waker is only woken once per poll
+        handler(error("second"));
+        assert_eq!(wakes.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            poll_description(&mut errors, &mut cx),
+            task::Poll::Ready(Some("first".to_string()))
+        );
+        assert_eq!(
+            poll_description(&mut errors, &mut cx),
+            task::Poll::Ready(Some("second".to_string()))
+        );
+        assert_eq!(poll_description(&mut errors, &mut cx), task::Poll::Pending);
+    }
+
+    #[test]
+    fn uncaptured_errors_drain_after_close() {
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let waker = counting_waker(&wakes);
+        let mut cx = task::Context::from_waker(&waker);
+        let (mut errors, handler) = UncapturedErrors::new();
+
+        handler(error("first"));
+        handler(error("second"));
+        drop(handler);
+
+        // Errors raised before the handler was dropped are still yielded, in order
+        assert_eq!(
+            poll_description(&mut errors, &mut cx),
+            task::Poll::Ready(Some("first".to_string()))
+        );
+        assert_eq!(
+            poll_description(&mut errors, &mut cx),
+            task::Poll::Ready(Some("second".to_string()))
+        );
+        assert_eq!(
+            poll_description(&mut errors, &mut cx),
+            task::Poll::Ready(None)
+        );
+        assert_eq!(wakes.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn uncaptured_errors_end_on_replacement() {
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let waker = counting_waker(&wakes);
+        let mut cx = task::Context::from_waker(&waker);
+        let (mut errors, handler) = UncapturedErrors::new();
+
+        assert_eq!(poll_description(&mut errors, &mut cx), task::Poll::Pending);
+        // Replacing the handler drops the previous one, which wakes and ends the stream
+        drop(handler);
+        assert_eq!(wakes.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            poll_description(&mut errors, &mut cx),
+            task::Poll::Ready(None)
+        );
+    }
+
+    #[test]
+    fn uncaptured_errors_dropped_stream() {
+        let (errors, handler) = UncapturedErrors::new();
+        drop(errors);
+        // Logged instead of queued
+        handler(error("lost"));
+    }
 }
 
 trait BufferMappedRangeSlice {
@@ -1820,6 +2731,58 @@ impl AsMut<[u8]> for BufferViewMut<'_> {
     }
 }
 
+impl BufferView<'_> {
+    /// View the mapped bytes as a slice of `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the view is not a multiple of the size of `T`,
+    /// or if the mapped memory is not suitably aligned for `T`.
+    /// See [`BufferView::try_as_typed`].
+    pub fn as_typed<T: bytemuck::Pod>(&self) -> &[T] {
+        self.try_as_typed().unwrap_or_else(|err| {
+            panic!(
+                "Unable to view the mapping of buffer {:?} as [{}]: {:?}",
+                self.slice.buffer.id,
+                std::any::type_name::<T>(),
+                err
+            )
+        })
+    }
+
+    /// View the mapped bytes as a slice of `T`, returning an error if the size of the view
+    /// is not a multiple of the size of `T`, or if the mapped memory is not suitably aligned
+    /// for `T`.
+    pub fn try_as_typed<T: bytemuck::Pod>(&self) -> Result<&[T], bytemuck::PodCastError> {
+        cast_mapping(self.data.slice())
+    }
+}
+
+/// Views mapped bytes as a slice of `T`.
+fn cast_mapping<T: bytemuck::Pod>(bytes: &[u8]) -> Result<&[T], bytemuck::PodCastError> {
+    bytemuck::try_cast_slice(bytes)
+}
+
+impl BufferViewMut<'_> {
+    /// View the mapped bytes as a mutable slice of `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the view is not a multiple of the size of `T`,
+    /// or if the mapped memory is not suitably aligned for `T`.
+    pub fn as_typed_mut<T: bytemuck::Pod>(&mut self) -> &mut [T] {
+        let id = &self.slice.buffer.id;
+        bytemuck::try_cast_slice_mut(self.data.slice_mut()).unwrap_or_else(|err| {
+            panic!(
+                "Unable to view the mapping of buffer {:?} as [{}]: {:?}",
+                id,
+                std::any::type_name::<T>(),
+                err
+            )
+        })
+    }
+}
+
 impl Drop for BufferView<'_> {
     fn drop(&mut self) {
         self.slice
@@ -1872,6 +2835,29 @@ impl Buffer {
         Context::buffer_unmap(&*self.context, &self.id);
     }
 
+    /// Same as [`Buffer::unmap`], but fails instead of panicking when
+    /// views of the mapping are still alive.
+    pub fn try_unmap(&self) -> Result<(), MapError> {
+        self.map_context.lock().try_reset()?;
+        Context::buffer_unmap(&*self.context, &self.id);
+        Ok(())
+    }
+
+    /// Returns the size of the buffer in bytes.
+    pub fn size(&self) -> BufferAddress {
+        self.size
+    }
+
+    /// Returns the allowed usages of the buffer.
+    pub fn usage(&self) -> BufferUsage {
+        self.usage
+    }
+
+    /// Returns the current state of the host mapping of this buffer.
+    pub fn map_state(&self) -> MapState {
+        self.map_context.lock().state()
+    }
+
     /// Destroy the associated native resources as soon as possible.
     pub fn destroy(&self) {
         Context::buffer_destroy(&*self.context, &self.id);
@@ -1893,10 +2879,97 @@ impl<'a> BufferSlice<'a> {
         &self,
         mode: MapMode,
     ) -> impl Future<Output = Result<(), BufferAsyncError>> + Send {
+        let (future, completion) = backend::new_gpu_future();
+        self.map_async_with(mode, move |result| completion.complete(result));
+        future
+    }
+
+    /// Map the buffer, calling `callback` once the mapping is resolved.
+    ///
+    /// This is the same as [`BufferSlice::map_async`], without the need for an executor.
+    /// The callback is invoked from within `device.poll(...)` on native,
+    /// and from the browser event loop on the web.
+    pub fn map_async_with(
+        &self,
+        mode: MapMode,
+        callback: impl FnOnce(Result<(), BufferAsyncError>) + Send + 'static,
+    ) {
+        let range = self.begin_map();
+        let map_context = Arc::clone(&self.buffer.map_context);
+        Context::buffer_map_async_with(
+            &*self.buffer.context,
+            &self.buffer.id,
+            mode,
+            range.clone(),
+            move |result| {
+                map_context.lock().resolve(range, result.is_ok());
+                callback(result)
+            },
+        )
+    }
+
+    /// Map the slice with `mode` and wait for the mapping to resolve, polling `device`
+    /// as needed, returning a view of the slice.
+    ///
+    /// Use [`BufferSlice::map_write_blocking`] to get a mutable view instead.
+    ///
+    /// Blocks the current thread, so this is only available on native.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BufferAsyncError`] if the mapping failed, or if it could not resolve,
+    /// because the device is lost or the buffer is used by commands that are not submitted.
+    /// In the latter case, the buffer stays pending until the mapping resolves.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn map_blocking(
+        &self,
+        device: &Device,
+        mode: MapMode,
+    ) -> Result<BufferView<'a>, BufferAsyncError> {
+        self.map_and_wait(device, mode)?;
+        Ok(self.get_mapped_range())
+    }
+
+    /// Map the slice for writing and wait for the mapping to resolve, polling `device`
+    /// as needed, returning a mutable view of the slice.
+    ///
+    /// Blocks the current thread, so this is only available on native.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`BufferSlice::map_blocking`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn map_write_blocking(
+        &self,
+        device: &Device,
+    ) -> Result<BufferViewMut<'a>, BufferAsyncError> {
+        self.map_and_wait(device, MapMode::Write)?;
+        Ok(self.get_mapped_range_mut())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn map_and_wait(&self, device: &Device, mode: MapMode) -> Result<(), BufferAsyncError> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.map_async_with(mode, move |result| {
+            // The receiver may be gone if the mapping didn't resolve in time
+            let _ = sender.send(result);
+        });
+        // Waiting for the device completes all the submitted work and resolves the
+        // mappings that don't depend on other work. If the mapping is not resolved
+        // after that, the device is lost or the buffer is used by unsubmitted
+        // commands, and polling again would make no progress.
+        device.poll(Maintain::Wait);
+        match receiver.try_recv() {
+            Ok(result) => result,
+            Err(_) => Err(BufferAsyncError),
+        }
+    }
+
+    fn begin_map(&self) -> Range<BufferAddress> {
         let mut mc = self.buffer.map_context.lock();
         assert_eq!(
-            mc.initial_range,
-            0..0,
+            mc.state,
+            MapState::Unmapped,
             "Buffer {:?} is already mapped",
             self.buffer.id
         );
@@ -1904,42 +2977,51 @@ impl<'a> BufferSlice<'a> {
             Some(s) => self.offset + s.get(),
             None => mc.total_size,
         };
-        mc.initial_range = self.offset..end;
-
-        Context::buffer_map_async(
-            &*self.buffer.context,
-            &self.buffer.id,
-            mode,
-            self.offset..end,
-        )
+        mc.state = MapState::Pending;
+        self.offset..end
     }
 
     /// Synchronously and immediately map a buffer for reading. If the buffer is not immediately mappable
     /// through [`BufferDescriptor::mapped_at_creation`] or [`BufferSlice::map_async`], will panic.
     pub fn get_mapped_range(&self) -> BufferView<'a> {
-        let end = self.buffer.map_context.lock().add(self.offset, self.size);
-        let data = Context::buffer_get_mapped_range(
-            &*self.buffer.context,
-            &self.buffer.id,
-            self.offset..end,
-        );
-        BufferView { slice: *self, data }
+        self.try_get_mapped_range()
+            .unwrap_or_else(|err| panic!("Unable to get the mapped range: {}", err))
+    }
+
+    /// Registers the slice as a view of the mapping, and gets its contents from the backend.
+    fn mapped_data(&self) -> Result<BufferMappedRange, MapError> {
+        let mut map_context = self.buffer.map_context.lock();
+        let end = map_context.try_add(self.offset, self.size)?;
+        Context::buffer_get_mapped_range(&*self.buffer.context, &self.buffer.id, self.offset..end)
+            .map_err(|err| {
+                map_context.remove(self.offset, self.size);
+                err
+            })
+    }
+
+    /// Same as [`BufferSlice::get_mapped_range`], but fails instead of panicking
+    /// when the slice is not mapped or intersects another live view.
+    pub fn try_get_mapped_range(&self) -> Result<BufferView<'a>, MapError> {
+        let data = self.mapped_data()?;
+        Ok(BufferView { slice: *self, data })
     }
 
     /// Synchronously and immediately map a buffer for writing. If the buffer is not immediately mappable
     /// through [`BufferDescriptor::mapped_at_creation`] or [`BufferSlice::map_async`], will panic.
     pub fn get_mapped_range_mut(&self) -> BufferViewMut<'a> {
-        let end = self.buffer.map_context.lock().add(self.offset, self.size);
-        let data = Context::buffer_get_mapped_range(
-            &*self.buffer.context,
-            &self.buffer.id,
-            self.offset..end,
-        );
-        BufferViewMut {
+        self.try_get_mapped_range_mut()
+            .unwrap_or_else(|err| panic!("Unable to get the mapped range: {}", err))
+    }
+
+    /// Same as [`BufferSlice::get_mapped_range_mut`], but fails instead of panicking
+    /// when the slice is not mapped or intersects another live view.
+    pub fn try_get_mapped_range_mut(&self) -> Result<BufferViewMut<'a>, MapError> {
+        let data = self.mapped_data()?;
+        Ok(BufferViewMut {
             slice: *self,
             data,
             readable: self.buffer.usage.contains(BufferUsage::MAP_READ),
-        }
+        })
     }
 }
 
@@ -1953,14 +3035,73 @@ impl Drop for Buffer {
 
 impl Texture {
     /// Creates a view of this texture.
+    #[cfg_attr(feature = "track-caller", track_caller)]
     pub fn create_view(&self, desc: &TextureViewDescriptor) -> TextureView {
+        let id = Context::texture_create_view(&*self.context, &self.id, desc);
+        #[cfg(feature = "track-caller")]
+        self.context
+            .record_creation_site(&id, std::panic::Location::caller());
+        let layer_count = match self.dimension {
+            TextureDimension::D3 => 1,
+            TextureDimension::D1 | TextureDimension::D2 => self.size.depth_or_array_layers,
+        };
         TextureView {
             context: Arc::clone(&self.context),
-            id: Context::texture_create_view(&*self.context, &self.id, desc),
+            id,
+            format: desc.format.unwrap_or(self.format),
+            dimension: desc.dimension.unwrap_or(match self.dimension {
+                TextureDimension::D1 => TextureViewDimension::D1,
+                TextureDimension::D2 if layer_count == 1 => TextureViewDimension::D2,
+                TextureDimension::D2 => TextureViewDimension::D2Array,
+                TextureDimension::D3 => TextureViewDimension::D3,
+            }),
+            aspect: desc.aspect,
+            mip_levels: desc.base_mip_level
+                ..desc.base_mip_level
+                    + desc.mip_level_count.map_or(
+                        self.mip_level_count.saturating_sub(desc.base_mip_level),
+                        NonZeroU32::get,
+                    ),
+            array_layers: desc.base_array_layer
+                ..desc.base_array_layer
+                    + desc.array_layer_count.map_or(
+                        layer_count.saturating_sub(desc.base_array_layer),
+                        NonZeroU32::get,
+                    ),
             owned: true,
         }
     }
 
+    /// Returns the size of the texture, as given at creation.
+    pub fn size(&self) -> Extent3d {
+        self.size
+    }
+
+    /// Returns the format of the texture.
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Returns the number of mip levels of the texture.
+    pub fn mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+    /// Returns the number of samples per texel of the texture.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Returns the dimension of the texture.
+    pub fn dimension(&self) -> TextureDimension {
+        self.dimension
+    }
+
+    /// Returns the allowed usages of the texture.
+    pub fn usage(&self) -> TextureUsage {
+        self.usage
+    }
+
     /// Destroy the associated native resources as soon as possible.
     pub fn destroy(&self) {
         Context::texture_destroy(&*self.context, &self.id);
@@ -1975,6 +3116,33 @@ impl Drop for Texture {
     }
 }
 
+impl TextureView {
+    /// Returns the format of the view.
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Returns the dimension of the view.
+    pub fn dimension(&self) -> TextureViewDimension {
+        self.dimension
+    }
+
+    /// Returns the aspect of the texture seen by the view.
+    pub fn aspect(&self) -> TextureAspect {
+        self.aspect
+    }
+
+    /// Returns the range of mip levels of the texture seen by the view.
+    pub fn mip_levels(&self) -> Range<u32> {
+        self.mip_levels.clone()
+    }
+
+    /// Returns the range of array layers of the texture seen by the view.
+    pub fn array_layers(&self) -> Range<u32> {
+        self.array_layers.clone()
+    }
+}
+
 impl Drop for TextureView {
     fn drop(&mut self) {
         if self.owned && !thread::panicking() {
@@ -2910,6 +4078,11 @@ impl SwapChain {
             view: TextureView {
                 context: Arc::clone(&self.context),
                 id,
+                format: self.format,
+                dimension: TextureViewDimension::D2,
+                aspect: TextureAspect::All,
+                mip_levels: 0..1,
+                array_layers: 0..1,
                 owned: false,
             },
             detail,
@@ -2935,6 +4108,253 @@ impl SwapChain {
 pub trait UncapturedErrorHandler: Fn(Error) + Send + 'static {}
 impl<T> UncapturedErrorHandler for T where T: Fn(Error) + Send + 'static {}
 
+#[derive(Debug, Default)]
+struct UncapturedErrorQueue {
+    errors: VecDeque<Error>,
+    waker: Option<task::Waker>,
+    closed: bool,
+}
+
+/// Feeds an [`UncapturedErrors`] stream from the uncaptured error handler.
+struct UncapturedErrorSender {
+    queue: Weak<Mutex<UncapturedErrorQueue>>,
+}
+
+impl UncapturedErrorSender {
+    fn send(&self, error: Error) {
+        match self.queue.upgrade() {
+            Some(queue) => {
+                let mut queue = queue.lock();
+                queue.errors.push_back(error);
+                if let Some(waker) = queue.waker.take() {
+                    waker.wake();
+                }
+            }
+            None => log::error!("Uncaptured wgpu error: {}", error),
+        }
+    }
+}
+
+impl Drop for UncapturedErrorSender {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.upgrade() {
+            let mut queue = queue.lock();
+            queue.closed = true;
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Stream of the errors that are not handled in error scopes.
+///
+/// Returned by [`Device::uncaptured_errors`].
+#[derive(Debug)]
+pub struct UncapturedErrors {
+    queue: Arc<Mutex<UncapturedErrorQueue>>,
+}
+
+impl UncapturedErrors {
+    /// Creates a stream, along with the uncaptured error handler feeding it.
+    fn new() -> (Self, impl UncapturedErrorHandler) {
+        let queue = Arc::new(Mutex::new(UncapturedErrorQueue::default()));
+        let sender = UncapturedErrorSender {
+            queue: Arc::downgrade(&queue),
+        };
+        (UncapturedErrors { queue }, move |error| sender.send(error))
+    }
+}
+
+impl futures_core::Stream for UncapturedErrors {
+    type Item = Error;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Option<Error>> {
+        let mut queue = self.queue.lock();
+        if let Some(error) = queue.errors.pop_front() {
+            task::Poll::Ready(Some(error))
+        } else if queue.closed {
+            task::Poll::Ready(None)
+        } else {
+            queue.waker = Some(cx.waker().clone());
+            task::Poll::Pending
+        }
+    }
+}
+
+/// Filter for error scopes.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ErrorFilter {
+    /// Catch only out-of-memory errors.
+    OutOfMemory,
+    /// Catch only validation errors.
+    Validation,
+}
+
+/// Kind of object an [`Error`] originates from, as returned by [`Error::kind`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// Creating, mapping or destroying a [`Buffer`].
+    Buffer,
+    /// Creating a [`Texture`] or a [`TextureView`].
+    Texture,
+    /// Creating a [`Sampler`].
+    Sampler,
+    /// Creating a [`ShaderModule`].
+    ShaderModule,
+    /// Creating a [`BindGroupLayout`].
+    BindGroupLayout,
+    /// Creating a [`BindGroup`].
+    BindGroup,
+    /// Creating a [`PipelineLayout`].
+    PipelineLayout,
+    /// Creating a [`RenderPipeline`] or a [`ComputePipeline`].
+    Pipeline,
+    /// Recording a [`RenderPass`] or a [`ComputePass`].
+    Pass,
+    /// Recording a [`RenderBundle`].
+    RenderBundle,
+    /// Copying between buffers and textures.
+    Transfer,
+    /// Creating a [`QuerySet`], or writing and resolving queries.
+    Query,
+    /// Recording or finishing a [`CommandEncoder`].
+    CommandEncoder,
+    /// Anything else.
+    Other,
+}
+
+/// Structured description of an [`Error`], as returned by [`Error::report`].
+///
+/// This carries the same information as the error description, in a form suitable
+/// for tooling. It implements `serde::Serialize` when the `serde` feature is enabled.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorReport {
+    /// The chain of errors, from the outermost to the root cause.
+    pub chain: Vec<ErrorReportEntry>,
+}
+
+/// One error of an [`ErrorReport`] chain.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorReportEntry {
+    /// Message of this error.
+    pub message: String,
+    /// Resources this error refers to.
+    pub resources: Vec<ErrorResource>,
+}
+
+/// Resource referred to by an [`ErrorReportEntry`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorResource {
+    /// Role the resource plays in the error, like `buffer` or `destination`.
+    pub role: String,
+    /// Type of the resource, if known.
+    pub resource_type: Option<ResourceType>,
+    /// Label of the resource, empty if it has none.
+    pub label: String,
+    /// Backend id of the resource, if known.
+    pub id: Option<ResourceId>,
+    /// Location in the source code where the resource was created.
+    ///
+    /// Only recorded with the `track-caller` feature.
+    pub created_at: Option<String>,
+}
+
+/// Type of a resource referred to by an [`ErrorResource`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResourceType {
+    /// [`Buffer`]
+    Buffer,
+    /// [`Texture`]
+    Texture,
+    /// [`TextureView`]
+    TextureView,
+    /// [`Sampler`]
+    Sampler,
+    /// [`ShaderModule`]
+    ShaderModule,
+    /// [`BindGroupLayout`]
+    BindGroupLayout,
+    /// [`BindGroup`]
+    BindGroup,
+    /// [`PipelineLayout`]
+    PipelineLayout,
+    /// [`RenderPipeline`]
+    RenderPipeline,
+    /// [`ComputePipeline`]
+    ComputePipeline,
+    /// [`CommandBuffer`] or [`CommandEncoder`]
+    CommandBuffer,
+    /// [`RenderBundle`]
+    RenderBundle,
+    /// [`QuerySet`]
+    QuerySet,
+    /// Any other resource.
+    Other,
+}
+
+/// Backend id of a resource referred to by an [`ErrorResource`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourceId {
+    /// Index of the resource in its registry.
+    pub index: u32,
+    /// Epoch of the index, incremented each time the index is reused.
+    pub epoch: u32,
+    /// Backend the resource belongs to.
+    #[cfg_attr(feature = "serde", serde(with = "serde_backend"))]
+    pub backend: Backend,
+}
+
+/// `wgt::Backend` only implements the serde traits with the `trace` and `replay` features,
+/// so it's (de)serialized by name.
+#[cfg(feature = "serde")]
+mod serde_backend {
+    use super::Backend;
+
+    pub fn serialize<S: serde::Serializer>(
+        backend: &Backend,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let name = match *backend {
+            Backend::Empty => "empty",
+            Backend::Vulkan => "vulkan",
+            Backend::Metal => "metal",
+            Backend::Dx12 => "dx12",
+            Backend::Dx11 => "dx11",
+            Backend::Gl => "gl",
+            Backend::BrowserWebGpu => "webgpu",
+        };
+        serializer.serialize_str(name)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Backend, D::Error> {
+        let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(match name.as_str() {
+            "empty" => Backend::Empty,
+            "vulkan" => Backend::Vulkan,
+            "metal" => Backend::Metal,
+            "dx12" => Backend::Dx12,
+            "dx11" => Backend::Dx11,
+            "gl" => Backend::Gl,
+            "webgpu" => Backend::BrowserWebGpu,
+            _ => {
+                return Err(serde::de::Error::custom(format!(
+                    "unknown backend `{}`",
+                    name
+                )))
+            }
+        })
+    }
+}
+
 /// Error type
 #[derive(Debug)]
 pub enum Error {
@@ -2952,6 +4372,44 @@ pub enum Error {
     },
 }
 
+impl Error {
+    /// Name of the operation that raised the error, like `Device::create_buffer`.
+    ///
+    /// Not available on the web.
+    pub fn operation(&self) -> Option<&str> {
+        backend::error_operation(self.inner())
+    }
+
+    /// Label of the object involved in the failed operation, if it has one.
+    ///
+    /// Not available on the web.
+    pub fn label(&self) -> Option<&str> {
+        backend::error_label(self.inner())
+    }
+
+    /// Kind of object the failed operation was working with.
+    ///
+    /// Always [`ErrorKind::Other`] on the web.
+    pub fn kind(&self) -> ErrorKind {
+        backend::error_kind(self.inner())
+    }
+
+    /// Structured description of the error, listing the chain of causes together with
+    /// the labels and ids of the resources involved.
+    ///
+    /// Not available on the web.
+    pub fn report(&self) -> Option<&ErrorReport> {
+        backend::error_report(self.inner())
+    }
+
+    fn inner(&self) -> &(dyn error::Error + 'static) {
+        match self {
+            Error::OutOfMemoryError { source } => source.as_ref(),
+            Error::ValidationError { source, .. } => source.as_ref(),
+        }
+    }
+}
+
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
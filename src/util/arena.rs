@@ -0,0 +1,433 @@
+use super::align_up;
+use crate::{
+    BindingResource, Buffer, BufferAddress, BufferBinding, BufferDescriptor, BufferSize,
+    BufferSlice, BufferUsage, CommandEncoder, Device, Queue, BIND_BUFFER_ALIGNMENT,
+    COPY_BUFFER_ALIGNMENT,
+};
+use std::{collections::HashMap, ops::Range};
+
+/// Free ranges of a buffer of `size` bytes.
+#[derive(Debug)]
+struct FreeList {
+    size: BufferAddress,
+    /// Sorted and not adjacent to each other.
+    ranges: Vec<Range<BufferAddress>>,
+}
+
+impl FreeList {
+    fn new(size: BufferAddress) -> Self {
+        FreeList {
+            size,
+            ranges: vec![0..size],
+        }
+    }
+
+    /// Find the first free range fitting `size` bytes at `alignment`, and reserve it.
+    fn allocate(&mut self, size: BufferAddress, alignment: BufferAddress) -> Option<BufferAddress> {
+        let index = self
+            .ranges
+            .iter()
+            .position(|range| align_up(range.start, alignment) + size <= range.end)?;
+        let range = self.ranges.remove(index);
+        let start = align_up(range.start, alignment);
+        let mut insert = index;
+        if range.start < start {
+            self.ranges.insert(insert, range.start..start);
+            insert += 1;
+        }
+        if start + size < range.end {
+            self.ranges.insert(insert, start + size..range.end);
+        }
+        Some(start)
+    }
+
+    /// Give back a reserved range, merging it with the neighboring free ranges.
+    fn free(&mut self, range: Range<BufferAddress>) {
+        let index = self
+            .ranges
+            .iter()
+            .position(|free| free.start > range.start)
+            .unwrap_or_else(|| self.ranges.len());
+        let merge_prev = index > 0 && self.ranges[index - 1].end == range.start;
+        let merge_next = index < self.ranges.len() && self.ranges[index].start == range.end;
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                let next = self.ranges.remove(index);
+                self.ranges[index - 1].end = next.end;
+            }
+            (true, false) => self.ranges[index - 1].end = range.end,
+            (false, true) => self.ranges[index].start = range.start,
+            (false, false) => self.ranges.insert(index, range),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ranges.len() == 1 && self.ranges[0] == (0..self.size)
+    }
+}
+
+struct Block {
+    buffer: Buffer,
+    free: FreeList,
+}
+
+impl Block {
+    fn new(device: &Device, usage: BufferUsage, size: BufferAddress) -> Self {
+        Block {
+            buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("arena"),
+                size,
+                usage,
+                mapped_at_creation: false,
+            }),
+            free: FreeList::new(size),
+        }
+    }
+}
+
+/// Offsets of ranges of `sizes` bytes placed one after the other at `alignment`,
+/// and the total size they cover.
+fn pack(
+    sizes: impl IntoIterator<Item = BufferAddress>,
+    alignment: BufferAddress,
+) -> (Vec<BufferAddress>, BufferAddress) {
+    let mut end = 0;
+    let offsets = sizes
+        .into_iter()
+        .map(|size| {
+            let offset = align_up(end, alignment);
+            end = offset + size;
+            offset
+        })
+        .collect();
+    (offsets, end)
+}
+
+/// Keep only the first live block of `blocks`, moving it to index 0.
+fn keep_first_block<T>(blocks: &mut Vec<Option<T>>) {
+    let first = blocks.drain(..).flatten().next();
+    blocks.extend(first.map(Some));
+}
+
+struct Allocation {
+    block: usize,
+    offset: BufferAddress,
+    size: BufferSize,
+}
+
+impl Allocation {
+    /// Range reserved in the block, padded for copies.
+    fn reserved_range(&self) -> Range<BufferAddress> {
+        self.offset..self.offset + align_up(self.size.get(), COPY_BUFFER_ALIGNMENT)
+    }
+}
+
+/// Handle to an allocation of a [`BufferArena`].
+///
+/// The allocation is only released by [`BufferArena::free`].
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct ArenaAllocation {
+    id: u64,
+}
+
+/// Buffer arena is a machine that sub-allocates small pieces of data, like meshes or
+/// uniforms, out of a few large buffers.
+///
+/// Allocations are placed at offsets aligned to [`COPY_BUFFER_ALIGNMENT`], or to
+/// [`BIND_BUFFER_ALIGNMENT`] if the arena is used for uniform or storage buffers,
+/// so they can be copied to, and bound with an offset. A larger alignment can be
+/// requested when creating the arena, see [`BufferArena::new`].
+///
+/// Freed space is reused by later allocations. As it may get fragmented over time,
+/// `defragment` can be called to compact all the allocations into a single buffer.
+pub struct BufferArena {
+    usage: BufferUsage,
+    block_size: BufferAddress,
+    alignment: BufferAddress,
+    blocks: Vec<Option<Block>>,
+    allocations: HashMap<u64, Allocation>,
+    next_id: u64,
+}
+
+impl BufferArena {
+    /// Create a new buffer arena.
+    ///
+    /// The `block_size` is the unit of internal buffer allocation. Allocations larger than
+    /// it get a buffer of their own. [`BufferUsage::COPY_SRC`] and [`BufferUsage::COPY_DST`]
+    /// are always added to `usage`, for writing and defragmenting the arena.
+    ///
+    /// The offsets of the allocations are aligned to the largest of `min_offset_alignment`
+    /// and the alignments listed on [`BufferArena`]. `Limits` doesn't report the offset
+    /// alignment of uniform and storage buffers yet, so pass the device's
+    /// `minUniformBufferOffsetAlignment` here if it's known to be larger, or 0.
+    ///
+    /// # Panics
+    ///
+    /// - If `min_offset_alignment` is neither 0 nor a power of two.
+    pub fn new(
+        usage: BufferUsage,
+        block_size: BufferAddress,
+        min_offset_alignment: BufferAddress,
+    ) -> Self {
+        assert!(
+            min_offset_alignment == 0 || min_offset_alignment.is_power_of_two(),
+            "Offset alignment must be a power of two"
+        );
+        let usage_alignment = if usage.intersects(BufferUsage::UNIFORM | BufferUsage::STORAGE) {
+            BIND_BUFFER_ALIGNMENT
+        } else {
+            COPY_BUFFER_ALIGNMENT
+        };
+        BufferArena {
+            usage: usage | BufferUsage::COPY_SRC | BufferUsage::COPY_DST,
+            block_size: align_up(block_size, COPY_BUFFER_ALIGNMENT),
+            alignment: usage_alignment.max(min_offset_alignment),
+            blocks: Vec::new(),
+            allocations: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Returns the alignment of the offsets of the allocations.
+    pub fn alignment(&self) -> BufferAddress {
+        self.alignment
+    }
+
+    /// Allocate `size` bytes in the arena.
+    pub fn allocate(&mut self, device: &Device, size: BufferSize) -> ArenaAllocation {
+        let reserved = align_up(size.get(), COPY_BUFFER_ALIGNMENT);
+        let alignment = self.alignment;
+
+        let found = self
+            .blocks
+            .iter_mut()
+            .enumerate()
+            .find_map(|(index, block)| {
+                let offset = block.as_mut()?.free.allocate(reserved, alignment)?;
+                Some((index, offset))
+            });
+        let (block, offset) = match found {
+            Some(found) => found,
+            None => {
+                let mut new_block = Block::new(device, self.usage, self.block_size.max(reserved));
+                let offset = new_block.free.allocate(reserved, alignment).unwrap();
+                let index = match self.blocks.iter().position(Option::is_none) {
+                    Some(index) => {
+                        self.blocks[index] = Some(new_block);
+                        index
+                    }
+                    None => {
+                        self.blocks.push(Some(new_block));
+                        self.blocks.len() - 1
+                    }
+                };
+                (index, offset)
+            }
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.allocations.insert(
+            id,
+            Allocation {
+                block,
+                offset,
+                size,
+            },
+        );
+        ArenaAllocation { id }
+    }
+
+    /// Release an allocation, so its space can be reused.
+    ///
+    /// Buffers that become empty are destroyed, except when their size is `block_size`.
+    pub fn free(&mut self, allocation: ArenaAllocation) {
+        let alloc = self
+            .allocations
+            .remove(&allocation.id)
+            .expect("Allocation doesn't belong to this arena");
+        let slot = &mut self.blocks[alloc.block];
+        let block = slot.as_mut().unwrap();
+        block.free.free(alloc.reserved_range());
+        if block.free.is_empty() && block.free.size != self.block_size {
+            *slot = None;
+        }
+    }
+
+    fn get(&self, allocation: &ArenaAllocation) -> (&Buffer, &Allocation) {
+        let alloc = self
+            .allocations
+            .get(&allocation.id)
+            .expect("Allocation doesn't belong to this arena");
+        (&self.blocks[alloc.block].as_ref().unwrap().buffer, alloc)
+    }
+
+    /// Returns the buffer holding an allocation.
+    ///
+    /// It may change after `defragment`.
+    pub fn buffer(&self, allocation: &ArenaAllocation) -> &Buffer {
+        self.get(allocation).0
+    }
+
+    /// Returns the offset of an allocation in its buffer.
+    ///
+    /// It may change after `defragment`.
+    pub fn offset(&self, allocation: &ArenaAllocation) -> BufferAddress {
+        self.get(allocation).1.offset
+    }
+
+    /// Returns the size of an allocation.
+    pub fn size(&self, allocation: &ArenaAllocation) -> BufferSize {
+        self.get(allocation).1.size
+    }
+
+    /// Returns the slice of the buffer covered by an allocation, for use as vertex or index data.
+    pub fn slice(&self, allocation: &ArenaAllocation) -> BufferSlice {
+        let (buffer, alloc) = self.get(allocation);
+        buffer.slice(alloc.offset..alloc.offset + alloc.size.get())
+    }
+
+    /// Returns the binding of the buffer range covered by an allocation.
+    pub fn binding(&self, allocation: &ArenaAllocation) -> BindingResource {
+        let (buffer, alloc) = self.get(allocation);
+        BindingResource::Buffer(BufferBinding {
+            buffer,
+            offset: alloc.offset,
+            size: Some(alloc.size),
+        })
+    }
+
+    /// Schedule writing `data` at the start of an allocation.
+    ///
+    /// See [`Queue::write_buffer`] for the details.
+    pub fn write(&self, queue: &Queue, allocation: &ArenaAllocation, data: &[u8]) {
+        let (buffer, alloc) = self.get(allocation);
+        assert!(
+            data.len() as BufferAddress <= alloc.size.get(),
+            "Data doesn't fit in the allocation"
+        );
+        queue.write_buffer(buffer, alloc.offset, data);
+    }
+
+    /// Compact all the allocations into a single buffer, if they aren't already.
+    ///
+    /// The copies are recorded into `encoder`, which must be submitted before the
+    /// allocations are used again. Returns `true` if allocations were moved, in which
+    /// case bind groups and buffer slices referring to them must be recreated.
+    ///
+    /// Without allocations, nothing is moved, and all the buffers but one are destroyed.
+    pub fn defragment(&mut self, device: &Device, encoder: &mut CommandEncoder) -> bool {
+        if self.allocations.is_empty() {
+            keep_first_block(&mut self.blocks);
+            return false;
+        }
+
+        let live_blocks = self.blocks.iter().filter(|block| block.is_some()).count();
+        let mut allocations = self.allocations.values_mut().collect::<Vec<_>>();
+        allocations.sort_by_key(|alloc| (alloc.block, alloc.offset));
+
+        let (offsets, total_size) = pack(
+            allocations.iter().map(|alloc| {
+                let range = alloc.reserved_range();
+                range.end - range.start
+            }),
+            self.alignment,
+        );
+        let compact = live_blocks <= 1
+            && allocations
+                .iter()
+                .zip(offsets.iter())
+                .all(|(alloc, &offset)| alloc.block == 0 && alloc.offset == offset);
+        if compact {
+            return false;
+        }
+
+        let mut new_block = Block::new(device, self.usage, self.block_size.max(total_size));
+        for (alloc, offset) in allocations.into_iter().zip(offsets) {
+            let range = alloc.reserved_range();
+            let size = range.end - range.start;
+            let reserved = new_block.free.allocate(size, self.alignment);
+            debug_assert_eq!(reserved, Some(offset));
+            let old_block = self.blocks[alloc.block].as_ref().unwrap();
+            encoder.copy_buffer_to_buffer(
+                &old_block.buffer,
+                range.start,
+                &new_block.buffer,
+                offset,
+                size,
+            );
+            alloc.block = 0;
+            alloc.offset = offset;
+        }
+        self.blocks = vec![Some(new_block)];
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{keep_first_block, pack, FreeList};
+
+    #[test]
+    fn allocate_aligns_and_splits() {
+        let mut list = FreeList::new(1024);
+        assert_eq!(list.allocate(4, 4), Some(0));
+        assert_eq!(list.allocate(8, 256), Some(256));
+        assert_eq!(list.ranges, vec![4..256, 264..1024]);
+        assert_eq!(list.allocate(4, 4), Some(4));
+        assert_eq!(list.allocate(1024, 4), None);
+    }
+
+    #[test]
+    fn free_coalesces() {
+        let mut list = FreeList::new(64);
+        let a = list.allocate(16, 4).unwrap();
+        let b = list.allocate(16, 4).unwrap();
+        let c = list.allocate(16, 4).unwrap();
+        assert_eq!(list.ranges, vec![48..64]);
+
+        // Neither neighbor is free
+        list.free(b..b + 16);
+        assert_eq!(list.ranges, vec![16..32, 48..64]);
+        // Merged with the next range
+        list.free(a..a + 16);
+        assert_eq!(list.ranges, vec![0..32, 48..64]);
+        // Merged with both ranges
+        list.free(c..c + 16);
+        assert_eq!(list.ranges, vec![0..64]);
+        assert!(list.is_empty());
+
+        let a = list.allocate(16, 4).unwrap();
+        let b = list.allocate(16, 4).unwrap();
+        list.free(a..a + 16);
+        // Merged with the previous range
+        list.free(b..b + 16);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn pack_for_defragment() {
+        let sizes = [4, 8, 256, 4];
+        assert_eq!(pack(sizes[..0].iter().copied(), 256), (vec![], 0));
+        assert_eq!(pack(sizes.iter().copied(), 4), (vec![0, 4, 12, 268], 272));
+        assert_eq!(
+            pack(sizes.iter().copied(), 256),
+            (vec![0, 256, 512, 768], 772)
+        );
+    }
+
+    #[test]
+    fn keep_first_live_block() {
+        let mut blocks = vec![None, Some(1), None, Some(2)];
+        keep_first_block(&mut blocks);
+        assert_eq!(blocks, [Some(1)]);
+
+        let mut blocks = vec![Some(1)];
+        keep_first_block(&mut blocks);
+        assert_eq!(blocks, [Some(1)]);
+
+        let mut blocks: Vec<Option<u32>> = vec![None, None];
+        keep_first_block(&mut blocks);
+        assert!(blocks.is_empty());
+    }
+}
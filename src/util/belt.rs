@@ -1,7 +1,10 @@
+use super::align_up;
 use crate::{
     Buffer, BufferAddress, BufferDescriptor, BufferSize, BufferUsage, BufferViewMut,
-    CommandEncoder, Device, MapMode,
+    CommandEncoder, Device, Extent3d, ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, MapMode,
+    MapState, COPY_BYTES_PER_ROW_ALIGNMENT,
 };
+use std::num::NonZeroU32;
 use std::pin::Pin;
 use std::task::{self, Poll};
 use std::{future::Future, sync::mpsc};
@@ -38,6 +41,119 @@ impl<F: Future<Output = ()>> Future for Join<F> {
     }
 }
 
+/// Amount of memory held by a [`StagingBelt`], in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StagingBeltStats {
+    /// Size of the chunks being written to, before `finish`.
+    pub active_bytes: BufferAddress,
+    /// Size of the chunks waiting for the GPU, between `finish` and the completion of `recall`.
+    pub closed_bytes: BufferAddress,
+    /// Size of the chunks ready to be reused.
+    pub free_bytes: BufferAddress,
+}
+
+impl StagingBeltStats {
+    /// Size of the free chunks that can be kept under `max_resident_size`, given the size
+    /// of the chunks in use, which are never released.
+    fn max_free_bytes(&self, max_resident_size: BufferAddress) -> BufferAddress {
+        max_resident_size.saturating_sub(self.active_bytes + self.closed_bytes)
+    }
+}
+
+/// Remove chunks, largest first, until their total size is at most `max_size`.
+///
+/// Returns the size of the chunks left.
+fn trim_chunks<T>(
+    chunks: &mut Vec<T>,
+    size: impl Fn(&T) -> BufferAddress,
+    max_size: BufferAddress,
+) -> BufferAddress {
+    let mut total: BufferAddress = chunks.iter().map(&size).sum();
+    if total <= max_size {
+        return total;
+    }
+    chunks.sort_by_key(&size);
+    while total > max_size {
+        match chunks.pop() {
+            Some(chunk) => total -= size(&chunk),
+            None => break,
+        }
+    }
+    total
+}
+
+/// Sends a recalled chunk back to the belt when dropped.
+///
+/// This happens once the chunk is mapped, or when the future of `recall` is dropped
+/// without being driven to completion, so the belt can still account for the chunk.
+struct RecalledChunk {
+    chunk: Option<Chunk>,
+    sender: mpsc::Sender<Chunk>,
+}
+
+impl Drop for RecalledChunk {
+    fn drop(&mut self) {
+        if let Some(chunk) = self.chunk.take() {
+            // The only possible error is the other side disconnecting, which is fine
+            let _ = self.sender.send(chunk);
+        }
+    }
+}
+
+/// Mapped staging memory of a texture upload, returned by [`StagingBelt::write_texture`].
+///
+/// Rows of texel blocks are written tightly packed, the padding of the copy is skipped.
+/// Rows of all the layers, or depth slices, follow each other.
+#[derive(Debug)]
+pub struct StagingTextureViewMut<'a> {
+    view: BufferViewMut<'a>,
+    bytes_per_row: usize,
+    padded_bytes_per_row: usize,
+}
+
+impl StagingTextureViewMut<'_> {
+    /// Number of bytes in a row of texel blocks.
+    pub fn bytes_per_row(&self) -> usize {
+        self.bytes_per_row
+    }
+
+    /// Number of rows of texel blocks, in all the layers.
+    pub fn row_count(&self) -> usize {
+        self.view.len() / self.padded_bytes_per_row
+    }
+
+    /// Returns row of texel blocks `index`, counting the rows of all the layers.
+    pub fn row_mut(&mut self, index: usize) -> &mut [u8] {
+        let start = index * self.padded_bytes_per_row;
+        &mut self.view[start..start + self.bytes_per_row]
+    }
+
+    /// Returns an iterator over the rows of texel blocks of all the layers.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        let bytes_per_row = self.bytes_per_row;
+        self.view
+            .chunks_mut(self.padded_bytes_per_row)
+            .map(move |row| &mut row[..bytes_per_row])
+    }
+
+    /// Copy tightly packed rows of texel blocks from `data`.
+    ///
+    /// # Panics
+    ///
+    /// - If the size of `data` is not `bytes_per_row() * row_count()`.
+    pub fn write(&mut self, data: &[u8]) {
+        assert_eq!(
+            data.len(),
+            self.bytes_per_row * self.row_count(),
+            "Data size doesn't match the size of the upload"
+        );
+        let bytes_per_row = self.bytes_per_row;
+        for (row, data_row) in self.rows_mut().zip(data.chunks(bytes_per_row)) {
+            row.copy_from_slice(data_row);
+        }
+    }
+}
+
 struct Chunk {
     buffer: Buffer,
     size: BufferAddress,
@@ -54,13 +170,22 @@ struct Chunk {
 /// - Write to buffers that need writing to using `write_buffer`.
 /// - Call `finish`.
 /// - Submit all command encoders used with `write_buffer`.
-/// - Call `recall`
+/// - Call `recall`, and drive the returned future, or call `recall_sync`.
+///
+/// By default, the belt keeps all of its chunks around for reuse. Use `set_max_resident_size`
+/// or `trim` to release idle chunks, for example after a loading spike.
 pub struct StagingBelt {
     chunk_size: BufferAddress,
+    /// Maximum size of all the chunks, above which free chunks are released.
+    max_resident_size: Option<BufferAddress>,
+    /// Size of the chunks that are being recalled, and not received yet.
+    in_flight_size: BufferAddress,
     /// Chunks that we are actively using for pending transfers at this moment.
     active_chunks: Vec<Chunk>,
     /// Chunks that have scheduled transfers already.
     closed_chunks: Vec<Chunk>,
+    /// Chunks that are being mapped by `recall_sync`, or returned by the future of `recall`.
+    recalled_chunks: Vec<Chunk>,
     /// Chunks that are back from the GPU and ready to be used.
     free_chunks: Vec<Chunk>,
     sender: mpsc::Sender<Chunk>,
@@ -77,41 +202,69 @@ impl StagingBelt {
         let (sender, receiver) = mpsc::channel();
         StagingBelt {
             chunk_size,
+            max_resident_size: None,
+            in_flight_size: 0,
             active_chunks: Vec::new(),
             closed_chunks: Vec::new(),
+            recalled_chunks: Vec::new(),
             free_chunks: Vec::new(),
             sender,
             receiver,
         }
     }
 
-    /// Allocate the staging belt slice of `size` to be uploaded into the `target` buffer
-    /// at the specified offset.
+    /// Set the maximum size of all the chunks allocated by this belt.
     ///
-    /// The upload will be placed into the provided command encoder. This encoder
-    /// must be submitted after `finish` is called and before `recall` is called.
-    pub fn write_buffer(
+    /// This is not a hard limit: chunks in use are never released, and allocations
+    /// are not refused. Instead, free chunks are released whenever the total size exceeds it.
+    /// `None`, the default, means no limit.
+    pub fn set_max_resident_size(&mut self, max_resident_size: Option<BufferAddress>) {
+        self.max_resident_size = max_resident_size;
+        self.enforce_max_resident_size();
+    }
+
+    fn enforce_max_resident_size(&mut self) {
+        if let Some(max) = self.max_resident_size {
+            let max_free_size = self.stats().max_free_bytes(max);
+            self.trim(max_free_size);
+        }
+    }
+
+    /// Release free chunks, largest first, until at most `max_free_size` bytes of them are left.
+    pub fn trim(&mut self, max_free_size: BufferAddress) {
+        trim_chunks(&mut self.free_chunks, |chunk| chunk.size, max_free_size);
+    }
+
+    /// Returns the amount of memory held by this belt.
+    pub fn stats(&self) -> StagingBeltStats {
+        let sum = |chunks: &[Chunk]| chunks.iter().map(|chunk| chunk.size).sum::<BufferAddress>();
+        StagingBeltStats {
+            active_bytes: sum(&self.active_chunks),
+            closed_bytes: sum(&self.closed_chunks) + self.in_flight_size,
+            free_bytes: sum(&self.free_chunks),
+        }
+    }
+
+    /// Allocate `size` bytes at an offset aligned to `alignment` in one of the active chunks.
+    ///
+    /// Returns the offset of the allocation in the last active chunk.
+    fn allocate(
         &mut self,
-        encoder: &mut CommandEncoder,
-        target: &Buffer,
-        offset: BufferAddress,
-        size: BufferSize,
+        size: BufferAddress,
+        alignment: BufferAddress,
         device: &Device,
-    ) -> BufferViewMut {
+    ) -> BufferAddress {
+        self.receive_chunks();
         let mut chunk = if let Some(index) = self
             .active_chunks
             .iter()
-            .position(|chunk| chunk.offset + size.get() <= chunk.size)
+            .position(|chunk| align_up(chunk.offset, alignment) + size <= chunk.size)
         {
             self.active_chunks.swap_remove(index)
-        } else if let Some(index) = self
-            .free_chunks
-            .iter()
-            .position(|chunk| size.get() <= chunk.size)
-        {
+        } else if let Some(index) = self.free_chunks.iter().position(|chunk| size <= chunk.size) {
             self.free_chunks.swap_remove(index)
         } else {
-            let size = self.chunk_size.max(size.get());
+            let size = self.chunk_size.max(size);
             Chunk {
                 buffer: device.create_buffer(&BufferDescriptor {
                     label: Some("staging"),
@@ -124,23 +277,88 @@ impl StagingBelt {
             }
         };
 
-        encoder.copy_buffer_to_buffer(&chunk.buffer, chunk.offset, target, offset, size.get());
-        let old_offset = chunk.offset;
-        chunk.offset += size.get();
-        let remainder = chunk.offset % crate::MAP_ALIGNMENT;
-        if remainder != 0 {
-            chunk.offset += crate::MAP_ALIGNMENT - remainder;
-        }
-
+        let offset = align_up(chunk.offset, alignment);
+        chunk.offset = offset + size;
         self.active_chunks.push(chunk);
-        self.active_chunks
-            .last()
-            .unwrap()
+        offset
+    }
+
+    /// Allocate the staging belt slice of `size` to be uploaded into the `target` buffer
+    /// at the specified offset.
+    ///
+    /// The upload will be placed into the provided command encoder. This encoder
+    /// must be submitted after `finish` is called and before `recall` is called.
+    pub fn write_buffer(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        target: &Buffer,
+        offset: BufferAddress,
+        size: BufferSize,
+        device: &Device,
+    ) -> BufferViewMut {
+        let chunk_offset = self.allocate(size.get(), crate::MAP_ALIGNMENT, device);
+        let chunk = self.active_chunks.last().unwrap();
+        encoder.copy_buffer_to_buffer(&chunk.buffer, chunk_offset, target, offset, size.get());
+        chunk
             .buffer
-            .slice(old_offset..old_offset + size.get())
+            .slice(chunk_offset..chunk_offset + size.get())
             .get_mapped_range_mut()
     }
 
+    /// Allocate the staging belt slice for an image of `size` to be uploaded into the `target` texture.
+    ///
+    /// The returned view accepts tightly packed rows of texel blocks, and places them at the
+    /// offsets required by the copy, with rows padded to [`COPY_BYTES_PER_ROW_ALIGNMENT`].
+    /// For block-compressed formats, `size` is rounded up to whole blocks.
+    ///
+    /// The upload will be placed into the provided command encoder. This encoder
+    /// must be submitted after `finish` is called and before `recall` is called.
+    pub fn write_texture(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        target: ImageCopyTexture,
+        size: Extent3d,
+        device: &Device,
+    ) -> StagingTextureViewMut {
+        let format = target.texture.format();
+        let format_info = format.describe();
+        let physical_size = size.physical_size(format);
+        let bytes_per_row = physical_size.width / format_info.block_dimensions.0 as u32
+            * format_info.block_size as u32;
+        let padded_bytes_per_row = super::padded_bytes_per_row(bytes_per_row);
+        let row_count = physical_size.height / format_info.block_dimensions.1 as u32
+            * size.depth_or_array_layers;
+        let total_size = padded_bytes_per_row as BufferAddress * row_count as BufferAddress;
+        assert_ne!(total_size, 0, "Texture uploads can not be empty");
+
+        let chunk_offset = self.allocate(
+            total_size,
+            COPY_BYTES_PER_ROW_ALIGNMENT as BufferAddress,
+            device,
+        );
+        let chunk = self.active_chunks.last().unwrap();
+        encoder.copy_buffer_to_texture(
+            ImageCopyBuffer {
+                buffer: &chunk.buffer,
+                layout: ImageDataLayout {
+                    offset: chunk_offset,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: NonZeroU32::new(physical_size.height),
+                },
+            },
+            target,
+            size,
+        );
+        StagingTextureViewMut {
+            view: chunk
+                .buffer
+                .slice(chunk_offset..chunk_offset + total_size)
+                .get_mapped_range_mut(),
+            bytes_per_row: bytes_per_row as usize,
+            padded_bytes_per_row: padded_bytes_per_row as usize,
+        }
+    }
+
     /// Prepare currently mapped buffers for use in a submission.
     ///
     /// At this point, all the partially used staging buffers are closed until
@@ -156,29 +374,139 @@ impl StagingBelt {
     ///
     /// This has to be called after the command encoders written to `write_buffer` are submitted!
     pub fn recall(&mut self) -> impl Future<Output = ()> + Send {
-        while let Ok(mut chunk) = self.receiver.try_recv() {
-            chunk.offset = 0;
-            self.free_chunks.push(chunk);
-        }
+        self.receive_chunks();
 
+        self.in_flight_size += self
+            .closed_chunks
+            .iter()
+            .map(|chunk| chunk.size)
+            .sum::<BufferAddress>();
         let sender = &self.sender;
         let futures = self
             .closed_chunks
             .drain(..)
             .map(|chunk| {
-                let sender = sender.clone();
                 let async_buffer = chunk.buffer.slice(..).map_async(MapMode::Write);
+                let recalled = RecalledChunk {
+                    chunk: Some(chunk),
+                    sender: sender.clone(),
+                };
 
                 Some(async move {
-                    // The result is ignored
+                    // The result is observed through the map state of the buffer
                     async_buffer.await.ok();
-
-                    // The only possible error is the other side disconnecting, which is fine
-                    let _ = sender.send(chunk);
+                    drop(recalled);
                 })
             })
             .collect::<Vec<_>>();
 
         Join { futures }
     }
+
+    /// Recall all of the closed buffers back to be reused, without a future to drive.
+    ///
+    /// The buffers are mapped with callbacks, which are invoked by `Device::poll` on native,
+    /// and by the browser event loop on the web. They are reused by the belt as soon as
+    /// they are back, no further call is needed.
+    ///
+    /// This has to be called after the command encoders written to `write_buffer` are submitted!
+    pub fn recall_sync(&mut self) {
+        self.receive_chunks();
+
+        for chunk in self.closed_chunks.drain(..) {
+            self.in_flight_size += chunk.size;
+            // The result is observed through the map state of the buffer
+            chunk
+                .buffer
+                .slice(..)
+                .map_async_with(MapMode::Write, |_| ());
+            self.recalled_chunks.push(chunk);
+        }
+    }
+
+    /// Move the chunks that are back from the GPU to the free list.
+    fn receive_chunks(&mut self) {
+        self.recalled_chunks.extend(self.receiver.try_iter());
+        let mut index = 0;
+        while index < self.recalled_chunks.len() {
+            match self.recalled_chunks[index].buffer.map_state() {
+                MapState::Pending => index += 1,
+                MapState::Mapped(_) => {
+                    let mut chunk = self.recalled_chunks.swap_remove(index);
+                    self.in_flight_size -= chunk.size;
+                    chunk.offset = 0;
+                    self.free_chunks.push(chunk);
+                }
+                MapState::Unmapped => {
+                    // Mapping failed, so the chunk can't be reused
+                    let chunk = self.recalled_chunks.swap_remove(index);
+                    self.in_flight_size -= chunk.size;
+                }
+            }
+        }
+        self.enforce_max_resident_size();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{trim_chunks, StagingBeltStats};
+
+    #[test]
+    fn trim_largest_first() {
+        let mut chunks = vec![4, 16, 8, 2];
+        assert_eq!(trim_chunks(&mut chunks, |&size| size, 14), 14);
+        assert_eq!(chunks, [2, 4, 8]);
+    }
+
+    #[test]
+    fn trim_under_max() {
+        let mut chunks = vec![4, 16, 8];
+        assert_eq!(trim_chunks(&mut chunks, |&size| size, 28), 28);
+        assert_eq!(chunks, [4, 16, 8]);
+    }
+
+    #[test]
+    fn trim_all() {
+        let mut chunks = vec![4, 16, 8];
+        assert_eq!(trim_chunks(&mut chunks, |&size| size, 0), 0);
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn trim_more_than_needed() {
+        // Releasing the largest chunk is enough, even if it goes well below the max
+        let mut chunks = vec![1, 2, 64];
+        assert_eq!(trim_chunks(&mut chunks, |&size| size, 60), 3);
+        assert_eq!(chunks, [1, 2]);
+    }
+
+    #[test]
+    fn max_free_bytes() {
+        let stats = StagingBeltStats {
+            active_bytes: 16,
+            closed_bytes: 32,
+            free_bytes: 64,
+        };
+        assert_eq!(stats.max_free_bytes(100), 52);
+        assert_eq!(stats.max_free_bytes(48), 0);
+        // Chunks in use are never released, even above the cap
+        assert_eq!(stats.max_free_bytes(20), 0);
+    }
+
+    #[test]
+    fn cap_trims_free_chunks() {
+        let stats = StagingBeltStats {
+            active_bytes: 16,
+            closed_bytes: 16,
+            free_bytes: 48,
+        };
+        let mut free_chunks = vec![8, 32, 8];
+        let max_free_size = stats.max_free_bytes(64);
+        assert_eq!(
+            trim_chunks(&mut free_chunks, |&size| size, max_free_size),
+            16
+        );
+        assert_eq!(free_chunks, [8, 8]);
+    }
 }
@@ -1,4 +1,4 @@
-use std::{convert::TryFrom, num::NonZeroU32};
+use std::{error, fmt, num::NonZeroU32, ops::Range};
 
 /// Describes a [Buffer](crate::Buffer) when allocating.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -12,6 +12,75 @@ pub struct BufferInitDescriptor<'a> {
     pub usage: crate::BufferUsage,
 }
 
+/// Order in which the layers and mip levels of a texture are laid out in its data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextureDataOrder {
+    /// All the mip levels of the first layer, then all the mip levels of the next layer, and so on.
+    ///
+    /// Example:
+    /// Layer0Mip0 Layer0Mip1 Layer0Mip2 ... Layer1Mip0 Layer1Mip1 Layer1Mip2 ...
+    LayerMajor,
+    /// All the layers of the first mip level, then all the layers of the next mip level, and so on.
+    /// This is the order used by KTX2 files.
+    ///
+    /// Example:
+    /// Layer0Mip0 Layer1Mip0 Layer2Mip0 ... Layer0Mip1 Layer1Mip1 Layer2Mip1 ...
+    MipMajor,
+}
+
+impl Default for TextureDataOrder {
+    fn default() -> Self {
+        TextureDataOrder::LayerMajor
+    }
+}
+
+/// Error returned when texture data doesn't match the size of the texture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextureDataError {
+    /// The data ends before the data of a subresource does.
+    TooShort {
+        /// Length of the data in bytes.
+        len: usize,
+        /// Layer of the first subresource whose data is missing.
+        layer: u32,
+        /// Mip level of the first subresource whose data is missing.
+        mip_level: u32,
+        /// Range of the data of that subresource.
+        range: Range<usize>,
+    },
+    /// The data holds bytes past the end of the last subresource.
+    TooLong {
+        /// Length of the data in bytes.
+        len: usize,
+        /// Size of the data of the whole texture in bytes.
+        expected: usize,
+    },
+}
+
+impl fmt::Display for TextureDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TextureDataError::TooShort {
+                len,
+                layer,
+                mip_level,
+                ref range,
+            } => write!(
+                f,
+                "Texture data of {} bytes is too short: layer {} of mip level {} needs bytes {:?}",
+                len, layer, mip_level, range
+            ),
+            TextureDataError::TooLong { len, expected } => write!(
+                f,
+                "Texture data of {} bytes is longer than the {} bytes of the texture",
+                len, expected
+            ),
+        }
+    }
+}
+
+impl error::Error for TextureDataError {}
+
 /// Utility methods not meant to be in the main API.
 pub trait DeviceExt {
     /// Creates a [Buffer](crate::Buffer) with data to initialize it.
@@ -19,22 +88,69 @@ pub trait DeviceExt {
 
     /// Upload an entire texture and its mipmaps from a source buffer.
     ///
-    /// Expects all mipmaps to be tightly packed in the data buffer.
-    ///
-    /// If the texture is a 2DArray texture, uploads each layer in order, expecting
-    /// each layer and its mips to be tightly packed.
+    /// Expects all mipmaps to be tightly packed in the data buffer, with layers and mips
+    /// ordered as in [`TextureDataOrder::LayerMajor`].
     ///
-    /// Example:
-    /// Layer0Mip0 Layer0Mip1 Layer0Mip2 ... Layer1Mip0 Layer1Mip1 Layer1Mip2 ...
+    /// See [`DeviceExt::create_texture_with_data_ordered`] for the details.
     fn create_texture_with_data(
         &self,
         queue: &crate::Queue,
         desc: &crate::TextureDescriptor,
         data: &[u8],
     ) -> crate::Texture;
+
+    /// Upload an entire texture and its mipmaps from a source buffer, with layers and mips
+    /// in the given `order`.
+    ///
+    /// Each mip level of each layer is expected to be tightly packed, rows of texel blocks
+    /// one after the other. Mip levels of 3D textures hold all their depth slices, whose
+    /// count is halved at each level like the width and height.
+    ///
+    /// Cube maps are 2D textures with 6 layers, one per face in the order
+    /// +X, -X, +Y, -Y, +Z, -Z. Cube map arrays hold the faces of each cube one after the other.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `data` doesn't match the texture.
+    /// See [`DeviceExt::try_create_texture_with_data_ordered`].
+    fn create_texture_with_data_ordered(
+        &self,
+        queue: &crate::Queue,
+        desc: &crate::TextureDescriptor,
+        order: TextureDataOrder,
+        data: &[u8],
+    ) -> crate::Texture;
+
+    /// Upload an entire texture and its mipmaps from a source buffer, with layers and mips
+    /// ordered as in [`TextureDataOrder::LayerMajor`].
+    ///
+    /// See [`DeviceExt::try_create_texture_with_data_ordered`] for the details.
+    fn try_create_texture_with_data(
+        &self,
+        queue: &crate::Queue,
+        desc: &crate::TextureDescriptor,
+        data: &[u8],
+    ) -> Result<crate::Texture, TextureDataError>;
+
+    /// Upload an entire texture and its mipmaps from a source buffer, with layers and mips
+    /// in the given `order`.
+    ///
+    /// The layout of `data` is described in [`DeviceExt::create_texture_with_data_ordered`].
+    ///
+    /// Returns an error, without creating the texture, if `data` is too short for the
+    /// texture, naming the first layer and mip level whose data is missing, or if it holds
+    /// bytes past the end of the last mip level.
+    fn try_create_texture_with_data_ordered(
+        &self,
+        queue: &crate::Queue,
+        desc: &crate::TextureDescriptor,
+        order: TextureDataOrder,
+        data: &[u8],
+    ) -> Result<crate::Texture, TextureDataError>;
 }
 
 impl DeviceExt for crate::Device {
+    #[cfg_attr(feature = "track-caller", track_caller)]
     fn create_buffer_init(&self, descriptor: &BufferInitDescriptor<'_>) -> crate::Buffer {
         // Skip mapping if the buffer is zero sized
         if descriptor.contents.is_empty() {
@@ -73,78 +189,326 @@ impl DeviceExt for crate::Device {
         }
     }
 
+    #[cfg_attr(feature = "track-caller", track_caller)]
     fn create_texture_with_data(
         &self,
         queue: &crate::Queue,
         desc: &crate::TextureDescriptor,
         data: &[u8],
     ) -> crate::Texture {
+        self.create_texture_with_data_ordered(queue, desc, TextureDataOrder::LayerMajor, data)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn create_texture_with_data_ordered(
+        &self,
+        queue: &crate::Queue,
+        desc: &crate::TextureDescriptor,
+        order: TextureDataOrder,
+        data: &[u8],
+    ) -> crate::Texture {
+        match self.try_create_texture_with_data_ordered(queue, desc, order, data) {
+            Ok(texture) => texture,
+            Err(err) => panic!("Invalid data for texture of size {:?}: {}", desc.size, err),
+        }
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn try_create_texture_with_data(
+        &self,
+        queue: &crate::Queue,
+        desc: &crate::TextureDescriptor,
+        data: &[u8],
+    ) -> Result<crate::Texture, TextureDataError> {
+        self.try_create_texture_with_data_ordered(queue, desc, TextureDataOrder::LayerMajor, data)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn try_create_texture_with_data_ordered(
+        &self,
+        queue: &crate::Queue,
+        desc: &crate::TextureDescriptor,
+        order: TextureDataOrder,
+        data: &[u8],
+    ) -> Result<crate::Texture, TextureDataError> {
+        let subresources = subresource_layout(desc, order);
+        check_data_len(&subresources, data.len())?;
+
         let texture = self.create_texture(desc);
 
-        let format_info = desc.format.describe();
+        for sub in subresources {
+            queue.write_texture(
+                crate::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: sub.mip,
+                    origin: crate::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: sub.layer,
+                    },
+                },
+                &data[sub.range],
+                crate::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(
+                        NonZeroU32::new(sub.bytes_per_row).expect("invalid bytes per row"),
+                    ),
+                    rows_per_image: Some(NonZeroU32::new(sub.size.height).expect("invalid height")),
+                },
+                sub.size,
+            );
+        }
 
-        let (layer_iterations, mip_extent) = if desc.dimension == crate::TextureDimension::D3 {
-            (1, desc.size)
-        } else {
-            (
-                desc.size.depth_or_array_layers,
-                crate::Extent3d {
-                    depth_or_array_layers: 1,
-                    ..desc.size
+        Ok(texture)
+    }
+}
+
+/// Data of one mip level of one layer of a texture.
+#[derive(Debug, PartialEq)]
+struct Subresource {
+    layer: u32,
+    mip: u32,
+    /// Physical size of the mip level, including all the depth slices of 3D textures.
+    size: crate::Extent3d,
+    bytes_per_row: u32,
+    /// Range of the data of the subresource in the texture data.
+    range: Range<usize>,
+}
+
+/// Returns where the data of each subresource is in tightly packed texture data,
+/// in the order the subresources are laid out.
+fn subresource_layout(
+    desc: &crate::TextureDescriptor,
+    order: TextureDataOrder,
+) -> Vec<Subresource> {
+    let format_info = desc.format.describe();
+    let is_3d = desc.dimension == crate::TextureDimension::D3;
+
+    // 3D textures are uploaded as a single layer holding all the depth slices.
+    let layer_count = if is_3d {
+        1
+    } else {
+        desc.size.depth_or_array_layers
+    };
+
+    let mips = (0..desc.mip_level_count)
+        .map(|mip| {
+            let mip_size = crate::Extent3d {
+                width: (desc.size.width >> mip).max(1),
+                height: (desc.size.height >> mip).max(1),
+                depth_or_array_layers: if is_3d {
+                    (desc.size.depth_or_array_layers >> mip).max(1)
+                } else {
+                    1
                 },
-            )
-        };
-
-        let mip_level_count =
-            u8::try_from(desc.mip_level_count).expect("mip level count overflows a u8");
-
-        let mut binary_offset = 0;
-        for layer in 0..layer_iterations {
-            for mip in 0..mip_level_count {
-                let mip_size = mip_extent.at_mip_level(mip).unwrap();
-
-                // When uploading mips of compressed textures and the mip is supposed to be
-                // a size that isn't a multiple of the block size, the mip needs to be uploaded
-                // as its "physical size" which is the size rounded up to the nearest block size.
-                let mip_physical = mip_size.physical_size(desc.format);
-
-                // All these calculations are performed on the physical size as that's the
-                // data that exists in the buffer.
-                let width_blocks = mip_physical.width / format_info.block_dimensions.0 as u32;
-                let height_blocks = mip_physical.height / format_info.block_dimensions.1 as u32;
-
-                let bytes_per_row = width_blocks * format_info.block_size as u32;
-                let data_size = bytes_per_row * height_blocks * mip_extent.depth_or_array_layers;
-
-                let end_offset = binary_offset + data_size as usize;
-
-                queue.write_texture(
-                    crate::ImageCopyTexture {
-                        texture: &texture,
-                        mip_level: mip as u32,
-                        origin: crate::Origin3d {
-                            x: 0,
-                            y: 0,
-                            z: layer,
-                        },
-                    },
-                    &data[binary_offset..end_offset],
-                    crate::ImageDataLayout {
-                        offset: 0,
-                        bytes_per_row: Some(
-                            NonZeroU32::new(bytes_per_row).expect("invalid bytes per row"),
-                        ),
-                        rows_per_image: Some(
-                            NonZeroU32::new(mip_physical.height).expect("invalid height"),
-                        ),
-                    },
-                    mip_physical,
-                );
+            };
+
+            // When uploading mips of compressed textures and the mip is supposed to be
+            // a size that isn't a multiple of the block size, the mip needs to be uploaded
+            // as its "physical size" which is the size rounded up to the nearest block size.
+            let mip_physical = mip_size.physical_size(desc.format);
+
+            // All these calculations are performed on the physical size as that's the
+            // data that exists in the buffer.
+            let width_blocks = mip_physical.width / format_info.block_dimensions.0 as u32;
+            let height_blocks = mip_physical.height / format_info.block_dimensions.1 as u32;
+
+            let bytes_per_row = width_blocks * format_info.block_size as u32;
+            let data_size = bytes_per_row as usize
+                * height_blocks as usize
+                * mip_physical.depth_or_array_layers as usize;
+
+            (mip_physical, bytes_per_row, data_size)
+        })
+        .collect::<Vec<_>>();
+
+    let (outer_count, inner_count) = match order {
+        TextureDataOrder::LayerMajor => (layer_count, desc.mip_level_count),
+        TextureDataOrder::MipMajor => (desc.mip_level_count, layer_count),
+    };
 
-                binary_offset = end_offset;
+    let mut subresources = Vec::with_capacity((outer_count * inner_count) as usize);
+    let mut binary_offset = 0;
+    for outer in 0..outer_count {
+        for inner in 0..inner_count {
+            let (layer, mip) = match order {
+                TextureDataOrder::LayerMajor => (outer, inner),
+                TextureDataOrder::MipMajor => (inner, outer),
+            };
+            let (size, bytes_per_row, data_size) = mips[mip as usize];
+            subresources.push(Subresource {
+                layer,
+                mip,
+                size,
+                bytes_per_row,
+                range: binary_offset..binary_offset + data_size,
+            });
+            binary_offset += data_size;
+        }
+    }
+    subresources
+}
+
+/// Checks that texture data of `len` bytes holds exactly the given subresources.
+fn check_data_len(subresources: &[Subresource], len: usize) -> Result<(), TextureDataError> {
+    if let Some(missing) = subresources.iter().find(|sub| sub.range.end > len) {
+        return Err(TextureDataError::TooShort {
+            len,
+            layer: missing.layer,
+            mip_level: missing.mip,
+            range: missing.range.clone(),
+        });
+    }
+    let expected = subresources
+        .iter()
+        .map(|sub| sub.range.end)
+        .max()
+        .unwrap_or(0);
+    if len > expected {
+        return Err(TextureDataError::TooLong { len, expected });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_data_len, subresource_layout, TextureDataError, TextureDataOrder};
+    use crate::{
+        Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage,
+        TextureViewDimension,
+    };
+
+    fn descriptor(
+        dimension: TextureDimension,
+        depth_or_array_layers: u32,
+    ) -> TextureDescriptor<'static> {
+        TextureDescriptor {
+            label: None,
+            size: Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers,
+            },
+            mip_level_count: 3,
+            sample_count: 1,
+            dimension,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsage::COPY_DST,
+        }
+    }
+
+    #[test]
+    fn cube_layout() {
+        // 6 faces, with mips of 64, 16 and 4 bytes
+        let desc = descriptor(TextureDimension::D2, 6);
+
+        let layer_major = subresource_layout(&desc, TextureDataOrder::LayerMajor);
+        assert_eq!(layer_major.len(), 18);
+        assert_eq!(layer_major.last().unwrap().range.end, 6 * 84);
+        let face = &layer_major[3];
+        assert_eq!((face.layer, face.mip, face.range.clone()), (1, 0, 84..148));
+
+        let mip_major = subresource_layout(&desc, TextureDataOrder::MipMajor);
+        assert_eq!(mip_major.last().unwrap().range.end, 6 * 84);
+        let face = &mip_major[7];
+        assert_eq!((face.layer, face.mip, face.range.clone()), (1, 1, 400..416));
+    }
+
+    #[test]
+    fn cube_array_layout() {
+        let desc = descriptor(TextureDimension::D2, 12);
+        let layout = subresource_layout(&desc, TextureDataOrder::LayerMajor);
+        assert_eq!(layout.len(), 36);
+        assert_eq!(layout.last().unwrap().range.end, 12 * 84);
+        // First face of the second cube
+        let face = &layout[6 * 3];
+        assert_eq!((face.layer, face.mip, face.range.start), (6, 0, 6 * 84));
+    }
+
+    #[test]
+    fn volume_layout() {
+        let desc = descriptor(TextureDimension::D3, 4);
+        let layout = subresource_layout(&desc, TextureDataOrder::LayerMajor);
+        let depths = layout
+            .iter()
+            .map(|sub| sub.size.depth_or_array_layers)
+            .collect::<Vec<_>>();
+        assert_eq!(depths, [4, 2, 1]);
+        assert_eq!(layout.last().unwrap().range.end, 256 + 32 + 4);
+    }
+
+    /// Number of array layers a texture needs to be viewed with `dimension`,
+    /// for `count` cubes.
+    fn cube_layers(dimension: TextureViewDimension, count: u32) -> u32 {
+        match dimension {
+            TextureViewDimension::Cube => 6,
+            TextureViewDimension::CubeArray => 6 * count,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn cube_view_layout() {
+        for &(dimension, count) in &[
+            (TextureViewDimension::Cube, 1),
+            (TextureViewDimension::CubeArray, 3),
+        ] {
+            let desc = descriptor(TextureDimension::D2, cube_layers(dimension, count));
+            for &order in &[TextureDataOrder::LayerMajor, TextureDataOrder::MipMajor] {
+                let layout = subresource_layout(&desc, order);
+                assert_eq!(layout.len() as u32, 6 * count * 3);
+                assert_eq!(layout.last().unwrap().range.end, (6 * count) as usize * 84);
+
+                // Each face of each cube is found once per mip level, with the size of the
+                // mip level, and the data of the subresources is contiguous.
+                for cube in 0..count {
+                    for face in 0..6 {
+                        let layer = cube * 6 + face;
+                        let mips = layout
+                            .iter()
+                            .filter(|sub| sub.layer == layer)
+                            .map(|sub| (sub.mip, sub.size.width, sub.range.len()))
+                            .collect::<Vec<_>>();
+                        assert_eq!(mips, [(0, 4, 64), (1, 2, 16), (2, 1, 4)]);
+                    }
+                }
+                for pair in layout.windows(2) {
+                    assert_eq!(pair[0].range.end, pair[1].range.start);
+                }
             }
         }
+    }
+
+    #[test]
+    fn data_len() {
+        let desc = descriptor(TextureDimension::D2, 6);
+        let layout = subresource_layout(&desc, TextureDataOrder::LayerMajor);
 
-        texture
+        assert_eq!(check_data_len(&layout, 6 * 84), Ok(()));
+        assert_eq!(
+            check_data_len(&layout, 6 * 84 - 1),
+            Err(TextureDataError::TooShort {
+                len: 6 * 84 - 1,
+                layer: 5,
+                mip_level: 2,
+                range: 6 * 84 - 4..6 * 84,
+            })
+        );
+        assert_eq!(
+            check_data_len(&layout, 100),
+            Err(TextureDataError::TooShort {
+                len: 100,
+                layer: 1,
+                mip_level: 0,
+                range: 84..148,
+            })
+        );
+        assert_eq!(
+            check_data_len(&layout, 6 * 84 + 1),
+            Err(TextureDataError::TooLong {
+                len: 6 * 84 + 1,
+                expected: 6 * 84,
+            })
+        );
     }
 }
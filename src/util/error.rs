@@ -0,0 +1,160 @@
+use crate::{Device, Error, ErrorKind, UncapturedErrorHandler};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Collects the errors of a [`Device`] that are not captured by an error scope.
+///
+/// Once installed, it replaces the uncaptured error handler of the device, so
+/// errors are stored for later inspection instead of panicking.
+/// This is mostly useful for tests and tools.
+///
+/// Not functional on the web yet, since uncaptured errors are not reported there.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorCollector {
+    errors: Arc<Mutex<Vec<Error>>>,
+}
+
+impl ErrorCollector {
+    /// Create a collector and install it as the uncaptured error handler of `device`.
+    pub fn install(device: &Device) -> Self {
+        let collector = Self::default();
+        device.on_uncaptured_error(collector.handler());
+        collector
+    }
+
+    /// Returns an uncaptured error handler storing the errors in this collector.
+    fn handler(&self) -> impl UncapturedErrorHandler {
+        let errors = Arc::clone(&self.errors);
+        move |error| errors.lock().push(error)
+    }
+
+    /// Returns the number of errors collected so far.
+    pub fn len(&self) -> usize {
+        self.errors.lock().len()
+    }
+
+    /// Returns `true` if no error has been collected.
+    pub fn is_empty(&self) -> bool {
+        self.errors.lock().is_empty()
+    }
+
+    /// Take all the errors collected so far.
+    pub fn take(&self) -> Vec<Error> {
+        std::mem::take(&mut *self.errors.lock())
+    }
+
+    /// Take the errors of the given kind, leaving the others in the collector.
+    pub fn take_kind(&self, kind: ErrorKind) -> Vec<Error> {
+        let mut errors = self.errors.lock();
+        let (taken, kept) = errors.drain(..).partition(|error| error.kind() == kind);
+        *errors = kept;
+        taken
+    }
+
+    /// Panics if any error has been collected, listing all of them.
+    #[track_caller]
+    pub fn assert_none(&self) {
+        let errors = self.take();
+        if !errors.is_empty() {
+            let descriptions = errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>();
+            panic!(
+                "{} wgpu error(s) collected:\n{}",
+                errors.len(),
+                descriptions.join("\n")
+            );
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::ErrorCollector;
+    use crate::{Error, ErrorKind};
+    use std::{
+        io,
+        panic::{self, AssertUnwindSafe},
+    };
+    use wgc::resource::{BufferAccessError, CreateTextureViewError};
+
+    fn validation(source: impl std::error::Error + Send + 'static) -> Error {
+        Error::ValidationError {
+            description: source.to_string(),
+            source: Box::new(source),
+        }
+    }
+
+    /// A collector with a buffer, a texture and an unclassified error pushed
+    /// through its handler, in that order.
+    fn collector() -> ErrorCollector {
+        let collector = ErrorCollector::default();
+        let handler = collector.handler();
+        handler(validation(BufferAccessError::Destroyed));
+        handler(validation(CreateTextureViewError::InvalidTexture));
+        handler(Error::OutOfMemoryError {
+            source: Box::new(io::Error::new(io::ErrorKind::Other, "out of memory")),
+        });
+        collector
+    }
+
+    #[test]
+    fn handler_collects() {
+        let collector = ErrorCollector::default();
+        assert!(collector.is_empty());
+
+        // Clones share the errors, like the handler installed on a device
+        let clone = collector.clone();
+        let handler = clone.handler();
+        handler(validation(BufferAccessError::Destroyed));
+        assert_eq!(collector.len(), 1);
+        assert!(!collector.is_empty());
+    }
+
+    #[test]
+    fn take() {
+        let collector = collector();
+        let kinds = collector.take().iter().map(Error::kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [ErrorKind::Buffer, ErrorKind::Texture, ErrorKind::Other]
+        );
+        assert!(collector.is_empty());
+        assert!(collector.take().is_empty());
+    }
+
+    #[test]
+    fn take_kind() {
+        let collector = collector();
+
+        let textures = collector.take_kind(ErrorKind::Texture);
+        assert_eq!(textures.len(), 1);
+        assert_eq!(textures[0].kind(), ErrorKind::Texture);
+        assert!(collector.take_kind(ErrorKind::Texture).is_empty());
+        assert!(collector.take_kind(ErrorKind::Sampler).is_empty());
+
+        // The other errors are kept in order
+        let kinds = collector.take().iter().map(Error::kind).collect::<Vec<_>>();
+        assert_eq!(kinds, [ErrorKind::Buffer, ErrorKind::Other]);
+    }
+
+    #[test]
+    fn assert_none_empty() {
+        ErrorCollector::default().assert_none();
+    }
+
+    #[test]
+    #[should_panic(expected = "3 wgpu error(s) collected")]
+    fn assert_none_with_errors() {
+        collector().assert_none();
+    }
+
+    #[test]
+    fn assert_none_takes_errors() {
+        let collector = collector();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| collector.assert_none()));
+        assert!(result.is_err());
+        assert!(collector.is_empty());
+    }
+}
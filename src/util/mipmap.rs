@@ -0,0 +1,456 @@
+use crate::{
+    Adapter, AddressMode, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Color,
+    CommandEncoder, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device,
+    Extent3d, Features, FilterMode, FragmentState, LoadOp, Operations, PipelineLayoutDescriptor,
+    PrimitiveState, PrimitiveTopology, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerDescriptor, ShaderFlags,
+    ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStage, StorageTextureAccess, Texture,
+    TextureAspect, TextureDimension, TextureFormat, TextureFormatFeatures, TextureSampleType,
+    TextureUsage, TextureView, TextureViewDescriptor, TextureViewDimension, VertexState,
+};
+use std::{borrow::Cow, collections::HashMap, error, fmt, num::NonZeroU32};
+
+/// Error occurred when trying to generate mipmaps.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MipmapError {
+    /// The format can neither be rendered to with filtering, nor written to as a storage texture.
+    UnsupportedFormat(TextureFormat),
+    /// Only 2D textures, including arrays and cube maps, are supported.
+    UnsupportedDimension(TextureDimension),
+    /// Multisampled textures have no mipmaps.
+    Multisampled,
+    /// The texture was not created with the usages needed for the supported method.
+    MissingUsage(TextureUsage),
+}
+
+impl fmt::Display for MipmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            MipmapError::UnsupportedFormat(format) => write!(
+                f,
+                "Format {:?} can't be rendered to or used as a storage texture",
+                format
+            ),
+            MipmapError::UnsupportedDimension(dimension) => {
+                write!(f, "Textures of dimension {:?} are not supported", dimension)
+            }
+            MipmapError::Multisampled => write!(f, "Multisampled textures have no mipmaps"),
+            MipmapError::MissingUsage(usage) => {
+                write!(f, "Texture is missing the usages {:?}", usage)
+            }
+        }
+    }
+}
+
+impl error::Error for MipmapError {}
+
+/// The way mipmaps of a format are generated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MipmapMethod {
+    /// Each level is rendered by sampling the previous one.
+    Render,
+    /// Each level is written by a compute shader, as a storage texture.
+    Compute,
+}
+
+impl MipmapMethod {
+    fn select(
+        format: TextureFormat,
+        features: &TextureFormatFeatures,
+    ) -> Result<Self, MipmapError> {
+        let filterable = matches!(
+            format.describe().sample_type,
+            TextureSampleType::Float { filterable: true }
+        );
+        if filterable
+            && features
+                .allowed_usages
+                .contains(TextureUsage::RENDER_ATTACHMENT)
+        {
+            Ok(MipmapMethod::Render)
+        } else if storage_format_name(format).is_some()
+            && features.allowed_usages.contains(TextureUsage::STORAGE)
+        {
+            Ok(MipmapMethod::Compute)
+        } else {
+            Err(MipmapError::UnsupportedFormat(format))
+        }
+    }
+
+    fn required_usage(self) -> TextureUsage {
+        match self {
+            MipmapMethod::Render => TextureUsage::SAMPLED | TextureUsage::RENDER_ATTACHMENT,
+            MipmapMethod::Compute => TextureUsage::SAMPLED | TextureUsage::STORAGE,
+        }
+    }
+}
+
+/// Name of the formats that can be written by the compute shader.
+fn storage_format_name(format: TextureFormat) -> Option<&'static str> {
+    Some(match format {
+        TextureFormat::Rgba8Unorm => "rgba8unorm",
+        TextureFormat::Rgba8Snorm => "rgba8snorm",
+        TextureFormat::Rgba16Float => "rgba16float",
+        TextureFormat::R32Float => "r32float",
+        TextureFormat::Rg32Float => "rg32float",
+        TextureFormat::Rgba32Float => "rgba32float",
+        _ => return None,
+    })
+}
+
+/// Generates the mip levels of textures from their first level.
+///
+/// The pipelines are created for each format on first use, and cached, so the generator
+/// should be kept around and reused for all the textures of a device.
+///
+/// Formats that can be rendered to with filtering are rendered, others are processed
+/// by a compute shader if they can be used as storage textures. Use
+/// [`MipmapGenerator::check_support`] to know which method applies on an adapter.
+#[derive(Debug)]
+pub struct MipmapGenerator {
+    sampler: Sampler,
+    blit_shader: ShaderModule,
+    render_pipelines: HashMap<TextureFormat, RenderPipeline>,
+    compute_pipelines: HashMap<TextureFormat, (BindGroupLayout, ComputePipeline)>,
+}
+
+impl MipmapGenerator {
+    /// Create a new mipmap generator.
+    pub fn new(device: &Device) -> Self {
+        MipmapGenerator {
+            sampler: device.create_sampler(&SamplerDescriptor {
+                label: Some("mipmap"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Nearest,
+                ..Default::default()
+            }),
+            blit_shader: device.create_shader_module(&ShaderModuleDescriptor {
+                label: Some("mipmap blit"),
+                source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("mipmap_blit.wgsl"))),
+                flags: ShaderFlags::all(),
+            }),
+            render_pipelines: HashMap::new(),
+            compute_pipelines: HashMap::new(),
+        }
+    }
+
+    /// Returns the method used to generate mipmaps of `format`, with the features of `adapter`.
+    pub fn check_support(
+        adapter: &Adapter,
+        format: TextureFormat,
+    ) -> Result<MipmapMethod, MipmapError> {
+        MipmapMethod::select(format, &adapter.get_texture_format_features(format))
+    }
+
+    /// Record the generation of all the mip levels of `texture` into `encoder`.
+    ///
+    /// The format support is checked against the features reported by
+    /// [`Adapter::get_texture_format_features`] if the device has
+    /// [`Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`],
+    /// and against the features guaranteed by WebGPU otherwise.
+    pub fn generate(
+        &mut self,
+        device: &Device,
+        adapter: &Adapter,
+        encoder: &mut CommandEncoder,
+        texture: &Texture,
+    ) -> Result<(), MipmapError> {
+        let format = texture.format();
+        let features = if device
+            .features()
+            .contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            adapter.get_texture_format_features(format)
+        } else {
+            format.describe().guaranteed_format_features
+        };
+        self.generate_with_features(device, encoder, texture, &features)
+    }
+
+    /// Record the generation of all the mip levels of `texture` into `encoder`,
+    /// given the features of its format, as returned by [`Adapter::get_texture_format_features`].
+    pub fn generate_with_features(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        texture: &Texture,
+        features: &TextureFormatFeatures,
+    ) -> Result<(), MipmapError> {
+        if texture.dimension() != TextureDimension::D2 {
+            return Err(MipmapError::UnsupportedDimension(texture.dimension()));
+        }
+        if texture.sample_count() > 1 {
+            return Err(MipmapError::Multisampled);
+        }
+        let format = texture.format();
+        let method = MipmapMethod::select(format, features)?;
+        let missing = method.required_usage() - texture.usage();
+        if !missing.is_empty() {
+            return Err(MipmapError::MissingUsage(missing));
+        }
+
+        for layer in 0..texture.size().depth_or_array_layers {
+            let views = (0..texture.mip_level_count())
+                .map(|mip| {
+                    texture.create_view(&TextureViewDescriptor {
+                        label: Some("mipmap"),
+                        format: None,
+                        dimension: Some(TextureViewDimension::D2),
+                        aspect: TextureAspect::All,
+                        base_mip_level: mip,
+                        mip_level_count: NonZeroU32::new(1),
+                        base_array_layer: layer,
+                        array_layer_count: NonZeroU32::new(1),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            match method {
+                MipmapMethod::Render => self.render_levels(device, encoder, format, &views),
+                MipmapMethod::Compute => {
+                    let mut size = texture.size();
+                    size.depth_or_array_layers = 1;
+                    self.compute_levels(device, encoder, format, size, &views)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn render_levels(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        format: TextureFormat,
+        views: &[TextureView],
+    ) {
+        let shader = &self.blit_shader;
+        let pipeline = self.render_pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("mipmap blit"),
+                layout: None,
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    targets: &[format.into()],
+                }),
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: Default::default(),
+            })
+        });
+        let bind_group_layout = pipeline.get_bind_group_layout(0);
+
+        for pair in views.windows(2) {
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: Some("mipmap"),
+                layout: &bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&pair[0]),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("mipmap"),
+                color_attachments: &[RenderPassColorAttachment {
+                    view: &pair[1],
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..4, 0..1);
+        }
+    }
+
+    fn compute_levels(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        format: TextureFormat,
+        size: Extent3d,
+        views: &[TextureView],
+    ) {
+        let (bind_group_layout, pipeline) =
+            self.compute_pipelines.entry(format).or_insert_with(|| {
+                // The source level is read with `textureLoad`, so the layout is explicit to
+                // accept formats that can't be filtered, like `R32Float`.
+                let bind_group_layout =
+                    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                        label: Some("mipmap compute"),
+                        entries: &[
+                            BindGroupLayoutEntry {
+                                binding: 0,
+                                visibility: ShaderStage::COMPUTE,
+                                ty: BindingType::Texture {
+                                    sample_type: TextureSampleType::Float { filterable: false },
+                                    view_dimension: TextureViewDimension::D2,
+                                    multisampled: false,
+                                },
+                                count: None,
+                            },
+                            BindGroupLayoutEntry {
+                                binding: 1,
+                                visibility: ShaderStage::COMPUTE,
+                                ty: BindingType::StorageTexture {
+                                    access: StorageTextureAccess::WriteOnly,
+                                    format,
+                                    view_dimension: TextureViewDimension::D2,
+                                },
+                                count: None,
+                            },
+                        ],
+                    });
+                let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: Some("mipmap compute"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+                let source = include_str!("mipmap_compute.wgsl")
+                    .replace("FORMAT", storage_format_name(format).unwrap());
+                let module = device.create_shader_module(&ShaderModuleDescriptor {
+                    label: Some("mipmap compute"),
+                    source: ShaderSource::Wgsl(Cow::Owned(source)),
+                    flags: ShaderFlags::all(),
+                });
+                let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+                    label: Some("mipmap compute"),
+                    layout: Some(&pipeline_layout),
+                    module: &module,
+                    entry_point: "main",
+                });
+                (bind_group_layout, pipeline)
+            });
+        let bind_groups = views
+            .windows(2)
+            .map(|pair| {
+                device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("mipmap"),
+                    layout: bind_group_layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(&pair[0]),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: BindingResource::TextureView(&pair[1]),
+                        },
+                    ],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("mipmap"),
+        });
+        pass.set_pipeline(pipeline);
+        for (index, bind_group) in bind_groups.iter().enumerate() {
+            let level_size = size.at_mip_level(index as u8 + 1).unwrap();
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch((level_size.width + 7) / 8, (level_size.height + 7) / 8, 1);
+        }
+    }
+}
+
+/// Record the generation of all the mip levels of `texture` into `encoder`.
+///
+/// The [`MipmapGenerator`] used is created on the first call and kept by `device`, so its
+/// pipelines are reused by later calls. The format support is checked against the features
+/// guaranteed by WebGPU; to use the features of an adapter with
+/// [`Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`], call [`MipmapGenerator::generate`].
+pub fn generate_mipmaps(
+    device: &Device,
+    encoder: &mut CommandEncoder,
+    texture: &Texture,
+) -> Result<(), MipmapError> {
+    let features = texture.format().describe().guaranteed_format_features;
+    device
+        .mipmap_generator
+        .lock()
+        .get_or_insert_with(|| MipmapGenerator::new(device))
+        .generate_with_features(device, encoder, texture, &features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextureFormatFeatureFlags;
+
+    fn with_usages(allowed_usages: TextureUsage) -> TextureFormatFeatures {
+        TextureFormatFeatures {
+            allowed_usages,
+            flags: TextureFormatFeatureFlags::empty(),
+        }
+    }
+
+    #[test]
+    fn select_render_for_filterable_attachments() {
+        let features = with_usages(TextureUsage::SAMPLED | TextureUsage::RENDER_ATTACHMENT);
+        assert_eq!(
+            MipmapMethod::select(TextureFormat::Rgba8UnormSrgb, &features),
+            Ok(MipmapMethod::Render)
+        );
+    }
+
+    #[test]
+    fn select_compute_for_unfilterable_storage() {
+        let features = with_usages(
+            TextureUsage::SAMPLED | TextureUsage::RENDER_ATTACHMENT | TextureUsage::STORAGE,
+        );
+        assert_eq!(
+            MipmapMethod::select(TextureFormat::R32Float, &features),
+            Ok(MipmapMethod::Compute)
+        );
+    }
+
+    #[test]
+    fn select_compute_without_render_attachment() {
+        let features = with_usages(TextureUsage::SAMPLED | TextureUsage::STORAGE);
+        assert_eq!(
+            MipmapMethod::select(TextureFormat::Rgba8Unorm, &features),
+            Ok(MipmapMethod::Compute)
+        );
+    }
+
+    #[test]
+    fn select_unsupported() {
+        let features = with_usages(TextureUsage::SAMPLED | TextureUsage::RENDER_ATTACHMENT);
+        assert_eq!(
+            MipmapMethod::select(TextureFormat::R32Float, &features),
+            Err(MipmapError::UnsupportedFormat(TextureFormat::R32Float))
+        );
+        let features = with_usages(
+            TextureUsage::SAMPLED | TextureUsage::RENDER_ATTACHMENT | TextureUsage::STORAGE,
+        );
+        assert_eq!(
+            MipmapMethod::select(TextureFormat::Rgba8Uint, &features),
+            Err(MipmapError::UnsupportedFormat(TextureFormat::Rgba8Uint))
+        );
+    }
+}
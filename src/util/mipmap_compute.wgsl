@@ -0,0 +1,21 @@
+[[group(0), binding(0)]]
+var r_src: texture_2d<f32>;
+[[group(0), binding(1)]]
+var r_dst: [[access(write)]] texture_storage_2d<FORMAT>;
+
+[[stage(compute), workgroup_size(8, 8)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+    let dst_size = textureDimensions(r_dst);
+    let coords = vec2<i32>(global_id.xy);
+    if (coords.x >= dst_size.x || coords.y >= dst_size.y) {
+        return;
+    }
+
+    let src_max = textureDimensions(r_src) - vec2<i32>(1, 1);
+    let base = coords * 2;
+    let sum = textureLoad(r_src, min(base, src_max), 0)
+        + textureLoad(r_src, min(base + vec2<i32>(1, 0), src_max), 0)
+        + textureLoad(r_src, min(base + vec2<i32>(0, 1), src_max), 0)
+        + textureLoad(r_src, min(base + vec2<i32>(1, 1), src_max), 0);
+    textureStore(r_dst, coords, sum * 0.25);
+}
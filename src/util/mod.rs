@@ -1,19 +1,36 @@
 //! Utility structures and functions.
 
+mod arena;
 mod belt;
 mod device;
 mod encoder;
+mod error;
+mod mipmap;
+mod readback;
+pub mod texel;
+mod typed;
+mod uniform;
+mod vec;
 
 use std::{
     borrow::Cow,
+    convert::TryFrom,
     future::Future,
     mem::{align_of, size_of},
+    num::NonZeroU32,
     ptr::copy_nonoverlapping,
 };
 
-pub use belt::StagingBelt;
-pub use device::{BufferInitDescriptor, DeviceExt};
+pub use arena::{ArenaAllocation, BufferArena};
+pub use belt::{StagingBelt, StagingBeltStats, StagingTextureViewMut};
+pub use device::{BufferInitDescriptor, DeviceExt, TextureDataError, TextureDataOrder};
 pub use encoder::RenderEncoder;
+pub use error::ErrorCollector;
+pub use mipmap::{generate_mipmaps, MipmapError, MipmapGenerator, MipmapMethod};
+pub use readback::{ReadbackBelt, ReadbackHandle};
+pub use typed::TypedBuffer;
+pub use uniform::DynamicUniformBuffer;
+pub use vec::GpuVec;
 
 /// Treat the given byte slice as a SPIR-V module.
 ///
@@ -56,6 +73,39 @@ pub fn make_spirv(data: &[u8]) -> super::ShaderSource {
     super::ShaderSource::SpirV(words)
 }
 
+/// Round `bytes_per_row` up to a multiple of [`COPY_BYTES_PER_ROW_ALIGNMENT`](crate::COPY_BYTES_PER_ROW_ALIGNMENT),
+/// as required for copies between buffers and textures.
+pub fn padded_bytes_per_row(bytes_per_row: u32) -> u32 {
+    align_up(
+        bytes_per_row.into(),
+        crate::COPY_BYTES_PER_ROW_ALIGNMENT.into(),
+    ) as u32
+}
+
+/// Round `value` up to a multiple of `alignment`.
+pub(crate) fn align_up(
+    value: super::BufferAddress,
+    alignment: super::BufferAddress,
+) -> super::BufferAddress {
+    (value + alignment - 1) / alignment * alignment
+}
+
+/// Returns the size of `T`, checking that elements of `T` can be copied individually.
+///
+/// # Panics
+///
+/// Panics if the size of `T` is not a non-zero multiple of [`COPY_BUFFER_ALIGNMENT`](crate::COPY_BUFFER_ALIGNMENT).
+pub(crate) fn element_size<T>() -> super::BufferAddress {
+    let size = size_of::<T>() as super::BufferAddress;
+    assert!(
+        size != 0 && size % crate::COPY_BUFFER_ALIGNMENT == 0,
+        "Size of {} ({}) is not a multiple of COPY_BUFFER_ALIGNMENT",
+        std::any::type_name::<T>(),
+        size
+    );
+    size
+}
+
 /// CPU accessible buffer used to download data back from the GPU.
 pub struct DownloadBuffer(super::Buffer, super::BufferMappedRange);
 
@@ -68,7 +118,7 @@ impl DownloadBuffer {
     ) -> impl Future<Output = Result<Self, super::BufferAsyncError>> + Send {
         let size = match buffer.size {
             Some(size) => size.into(),
-            None => buffer.buffer.map_context.lock().total_size - buffer.offset,
+            None => buffer.buffer.size - buffer.offset,
         };
 
         let download = device.create_buffer(&super::BufferDescriptor {
@@ -88,7 +138,8 @@ impl DownloadBuffer {
         async move {
             fut.await?;
             let mapped_range =
-                super::Context::buffer_get_mapped_range(&*download.context, &download.id, 0..size);
+                super::Context::buffer_get_mapped_range(&*download.context, &download.id, 0..size)
+                    .map_err(|_| super::BufferAsyncError)?;
             Ok(Self(download, mapped_range))
         }
     }
@@ -100,3 +151,370 @@ impl std::ops::Deref for DownloadBuffer {
         super::BufferMappedRangeSlice::slice(&self.1)
     }
 }
+
+/// Texture data downloaded back from the GPU, with rows tightly packed.
+#[derive(Debug)]
+pub struct DownloadTexture {
+    data: Vec<u8>,
+    format: super::TextureFormat,
+    levels: Vec<DownloadTextureLevel>,
+}
+
+/// Layout of one mip level in a [`DownloadTexture`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DownloadTextureLevel {
+    /// Mip level of the texture the data was read from.
+    pub mip_level: u32,
+    /// Extent of the level, in texels, rounded up to whole blocks.
+    pub size: super::Extent3d,
+    /// Offset of the level in the downloaded data.
+    pub offset: usize,
+    /// Number of bytes in a row of texel blocks in the downloaded data.
+    pub bytes_per_row: u32,
+    /// Number of bytes in a row of texel blocks in the copy from the texture, that is
+    /// `bytes_per_row` rounded up with [`padded_bytes_per_row`].
+    pub padded_bytes_per_row: u32,
+    /// Number of rows of texel blocks in each layer, or each depth slice.
+    pub rows_per_image: u32,
+}
+
+impl DownloadTextureLevel {
+    /// Number of bytes of the level in the downloaded data.
+    pub fn len(&self) -> usize {
+        self.bytes_per_row as usize
+            * self.rows_per_image as usize
+            * self.size.depth_or_array_layers as usize
+    }
+
+    /// Returns `true` if the level has no data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bytes of the level in the copy from the texture, with padded rows.
+    fn padded_len(&self) -> super::BufferAddress {
+        self.padded_bytes_per_row as super::BufferAddress
+            * self.rows_per_image as super::BufferAddress
+            * self.size.depth_or_array_layers as super::BufferAddress
+    }
+}
+
+/// Computes the layout of `mip_level_count` levels of `format`, starting at `base_mip_level`,
+/// for a region of `size` texels in the first level.
+///
+/// Returns the levels together with the size of the buffer they are copied to.
+fn download_levels(
+    format: super::TextureFormat,
+    size: super::Extent3d,
+    is_3d: bool,
+    base_mip_level: u32,
+    mip_level_count: u32,
+) -> (Vec<DownloadTextureLevel>, super::BufferAddress) {
+    let format_info = format.describe();
+    let (layers, mip_extent) = if is_3d {
+        (1, size)
+    } else {
+        (
+            size.depth_or_array_layers,
+            super::Extent3d {
+                depth_or_array_layers: 1,
+                ..size
+            },
+        )
+    };
+
+    let mut levels = Vec::with_capacity(mip_level_count as usize);
+    let mut offset = 0;
+    let mut buffer_size = 0;
+    for index in 0..mip_level_count {
+        let level_size = u8::try_from(index)
+            .ok()
+            .and_then(|index| mip_extent.at_mip_level(index))
+            .expect("mip level count exceeds the mip levels of the extent");
+        let physical_size = super::Extent3d {
+            depth_or_array_layers: level_size.depth_or_array_layers * layers,
+            ..level_size
+        }
+        .physical_size(format);
+        let width_blocks = physical_size.width / format_info.block_dimensions.0 as u32;
+        let height_blocks = physical_size.height / format_info.block_dimensions.1 as u32;
+
+        let bytes_per_row = width_blocks * format_info.block_size as u32;
+        let level = DownloadTextureLevel {
+            mip_level: base_mip_level + index,
+            size: physical_size,
+            offset,
+            bytes_per_row,
+            padded_bytes_per_row: padded_bytes_per_row(bytes_per_row),
+            rows_per_image: height_blocks,
+        };
+        offset += level.len();
+        buffer_size += level.padded_len();
+        levels.push(level);
+    }
+    (levels, buffer_size)
+}
+
+/// Copies the rows of `levels` out of `padded`, where they are stored one after the other
+/// with padded rows, into tightly packed rows.
+///
+/// The last row doesn't need to be padded.
+fn unpad_levels(padded: &[u8], levels: &[DownloadTextureLevel]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(levels.iter().map(DownloadTextureLevel::len).sum());
+    let mut buffer_offset = 0;
+    for level in levels {
+        let padded_end = (buffer_offset + level.padded_len() as usize).min(padded.len());
+        let level_data = &padded[buffer_offset..padded_end];
+        for row in level_data.chunks(level.padded_bytes_per_row as usize) {
+            data.extend_from_slice(&row[..level.bytes_per_row as usize]);
+        }
+        buffer_offset = padded_end;
+    }
+    data
+}
+
+impl DownloadTexture {
+    /// Asynchronously read a region of a texture.
+    ///
+    /// `size` is the extent of the region in texels, in the mip level given by `source`.
+    /// For block-compressed formats, it's rounded up to whole blocks. All the layers in
+    /// `size.depth_or_array_layers` are read, one after the other.
+    ///
+    /// The texture needs the [`TextureUsage::COPY_SRC`](super::TextureUsage::COPY_SRC) usage.
+    pub fn read_texture(
+        device: &super::Device,
+        queue: &super::Queue,
+        source: super::ImageCopyTexture,
+        size: super::Extent3d,
+    ) -> impl Future<Output = Result<Self, super::BufferAsyncError>> + Send {
+        Self::read_texture_levels(device, queue, source, size, 1)
+    }
+
+    /// Asynchronously read a region of `mip_level_count` consecutive mip levels of a texture,
+    /// starting at the mip level given by `source`.
+    ///
+    /// `size` is the extent of the region in the first mip level, and is halved for each
+    /// following level, like `source.origin`. Array layers are not halved. The levels are
+    /// stored one after the other, see [`DownloadTexture::levels`] for their layout.
+    ///
+    /// The texture needs the [`TextureUsage::COPY_SRC`](super::TextureUsage::COPY_SRC) usage.
+    ///
+    /// # Panics
+    ///
+    /// - If `mip_level_count` is zero.
+    /// - If `size` has fewer mip levels than `mip_level_count`.
+    pub fn read_texture_levels(
+        device: &super::Device,
+        queue: &super::Queue,
+        source: super::ImageCopyTexture,
+        size: super::Extent3d,
+        mip_level_count: u32,
+    ) -> impl Future<Output = Result<Self, super::BufferAsyncError>> + Send {
+        assert_ne!(mip_level_count, 0, "no mip level to read");
+        let format = source.texture.format();
+        let is_3d = source.texture.dimension() == super::TextureDimension::D3;
+        let (levels, buffer_size) =
+            download_levels(format, size, is_3d, source.mip_level, mip_level_count);
+
+        let download = device.create_buffer(&super::BufferDescriptor {
+            size: buffer_size,
+            usage: super::BufferUsage::COPY_DST | super::BufferUsage::MAP_READ,
+            mapped_at_creation: false,
+            label: None,
+        });
+
+        let mut encoder =
+            device.create_command_encoder(&super::CommandEncoderDescriptor { label: None });
+        let mut buffer_offset = 0;
+        for (index, level) in levels.iter().enumerate() {
+            let origin = super::Origin3d {
+                x: source.origin.x >> index,
+                y: source.origin.y >> index,
+                z: if is_3d {
+                    source.origin.z >> index
+                } else {
+                    source.origin.z
+                },
+            };
+            encoder.copy_texture_to_buffer(
+                super::ImageCopyTexture {
+                    texture: source.texture,
+                    mip_level: level.mip_level,
+                    origin,
+                },
+                super::ImageCopyBuffer {
+                    buffer: &download,
+                    layout: super::ImageDataLayout {
+                        offset: buffer_offset,
+                        bytes_per_row: NonZeroU32::new(level.padded_bytes_per_row),
+                        rows_per_image: NonZeroU32::new(level.size.height),
+                    },
+                },
+                level.size,
+            );
+            buffer_offset += level.padded_len();
+        }
+        let command_buffer: super::CommandBuffer = encoder.finish();
+        queue.submit(Some(command_buffer));
+
+        let fut = download.slice(..).map_async(super::MapMode::Read);
+        async move {
+            fut.await?;
+            let data = unpad_levels(&download.slice(..).get_mapped_range(), &levels);
+            download.unmap();
+            Ok(Self {
+                data,
+                format,
+                levels,
+            })
+        }
+    }
+
+    /// Returns the format of the texture the data was read from.
+    pub fn format(&self) -> super::TextureFormat {
+        self.format
+    }
+
+    /// Returns the extent of the data of the first mip level, in texels.
+    pub fn size(&self) -> super::Extent3d {
+        self.levels[0].size
+    }
+
+    /// Returns the number of bytes in a row of texel blocks of the first mip level.
+    pub fn bytes_per_row(&self) -> u32 {
+        self.levels[0].bytes_per_row
+    }
+
+    /// Returns the layout of each mip level that was read, in order.
+    pub fn levels(&self) -> &[DownloadTextureLevel] {
+        &self.levels
+    }
+
+    /// Returns the data of one of the [`levels`](DownloadTexture::levels), with rows of
+    /// texel blocks tightly packed.
+    pub fn level_data(&self, index: usize) -> &[u8] {
+        let level = &self.levels[index];
+        &self.data[level.offset..level.offset + level.len()]
+    }
+
+    /// Returns the data of all levels, with rows of texel blocks tightly packed.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl std::ops::Deref for DownloadTexture {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{download_levels, unpad_levels, DownloadTextureLevel};
+    use crate::{Extent3d, TextureFormat};
+
+    fn extent(width: u32, height: u32, depth_or_array_layers: u32) -> Extent3d {
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers,
+        }
+    }
+
+    #[test]
+    fn download_levels_pad_rows() {
+        let (levels, buffer_size) =
+            download_levels(TextureFormat::Rgba8Unorm, extent(3, 2, 2), false, 0, 1);
+        assert_eq!(
+            levels,
+            [DownloadTextureLevel {
+                mip_level: 0,
+                size: extent(3, 2, 2),
+                offset: 0,
+                bytes_per_row: 12,
+                padded_bytes_per_row: 256,
+                rows_per_image: 2,
+            }]
+        );
+        assert_eq!(levels[0].len(), 48);
+        assert_eq!(buffer_size, 256 * 2 * 2);
+
+        let (levels, buffer_size) =
+            download_levels(TextureFormat::Rgba8Unorm, extent(64, 1, 1), false, 0, 1);
+        assert_eq!(levels[0].bytes_per_row, 256);
+        assert_eq!(levels[0].padded_bytes_per_row, 256);
+        assert_eq!(buffer_size, 256);
+    }
+
+    #[test]
+    fn download_levels_compressed() {
+        let (levels, buffer_size) =
+            download_levels(TextureFormat::Bc1RgbaUnorm, extent(10, 10, 1), false, 1, 2);
+        assert_eq!(
+            levels,
+            [
+                DownloadTextureLevel {
+                    mip_level: 1,
+                    size: extent(12, 12, 1),
+                    offset: 0,
+                    bytes_per_row: 3 * 8,
+                    padded_bytes_per_row: 256,
+                    rows_per_image: 3,
+                },
+                DownloadTextureLevel {
+                    mip_level: 2,
+                    size: extent(8, 8, 1),
+                    offset: 3 * 3 * 8,
+                    bytes_per_row: 2 * 8,
+                    padded_bytes_per_row: 256,
+                    rows_per_image: 2,
+                },
+            ]
+        );
+        assert_eq!(buffer_size, 256 * 3 + 256 * 2);
+    }
+
+    #[test]
+    fn download_levels_3d() {
+        let (levels, buffer_size) =
+            download_levels(TextureFormat::Rgba8Unorm, extent(4, 4, 4), true, 0, 2);
+        assert_eq!(levels[0].size, extent(4, 4, 4));
+        assert_eq!(levels[1].size, extent(2, 2, 2));
+        assert_eq!(levels[1].offset, 16 * 4 * 4);
+        assert_eq!(levels[1].len(), 8 * 2 * 2);
+        assert_eq!(buffer_size, 256 * 4 * 4 + 256 * 2 * 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "mip level count exceeds the mip levels of the extent")]
+    fn download_levels_too_many() {
+        download_levels(TextureFormat::Rgba8Unorm, extent(2, 2, 1), false, 0, 3);
+    }
+
+    fn level(offset: usize, rows_per_image: u32) -> DownloadTextureLevel {
+        DownloadTextureLevel {
+            mip_level: 0,
+            size: extent(1, rows_per_image, 1),
+            offset,
+            bytes_per_row: 2,
+            padded_bytes_per_row: 4,
+            rows_per_image,
+        }
+    }
+
+    #[test]
+    fn unpad() {
+        let levels = [level(0, 2), level(4, 1)];
+        let padded = [1, 2, 0, 0, 3, 4, 0, 0, 5, 6, 0, 0];
+        assert_eq!(unpad_levels(&padded, &levels), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn unpad_unpadded_last_row() {
+        let levels = [level(0, 2), level(4, 1)];
+        let padded = [1, 2, 0, 0, 3, 4, 0, 0, 5, 6];
+        assert_eq!(unpad_levels(&padded, &levels), [1, 2, 3, 4, 5, 6]);
+    }
+}
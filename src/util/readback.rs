@@ -0,0 +1,415 @@
+use super::{align_up, download_levels, unpad_levels, DownloadTextureLevel};
+use crate::{
+    backend::{new_gpu_future, GpuFutureCompletion},
+    Buffer, BufferAddress, BufferAsyncError, BufferDescriptor, BufferSize, BufferUsage, BufferView,
+    CommandEncoder, Device, Extent3d, ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, MapMode,
+    TextureDimension, COPY_BYTES_PER_ROW_ALIGNMENT, MAP_ALIGNMENT,
+};
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    num::NonZeroU32,
+    ops::Range,
+    sync::{mpsc, Arc},
+};
+
+type MapResult = Result<(), BufferAsyncError>;
+
+/// Bookkeeping of a chunk, shared with the handles to its data.
+#[derive(Default)]
+struct ChunkState {
+    /// Number of live handles to data in the chunk.
+    handles: usize,
+    /// Set by `finish`, once no more data is allocated in the chunk.
+    closed: bool,
+    /// Result of the mapping, once it resolved.
+    result: Option<MapResult>,
+    /// Futures waiting for the mapping to resolve.
+    waiters: Vec<GpuFutureCompletion<MapResult>>,
+}
+
+impl ChunkState {
+    /// Returns `true` if the chunk can be reused, as no handle to its data is left.
+    fn is_unused(&self) -> bool {
+        self.closed && self.handles == 0
+    }
+
+    /// Record the result of the mapping, returning the futures to complete with it.
+    fn resolve(&mut self, result: MapResult) -> Vec<GpuFutureCompletion<MapResult>> {
+        self.result = Some(result);
+        std::mem::take(&mut self.waiters)
+    }
+}
+
+struct Chunk {
+    buffer: Buffer,
+    size: BufferAddress,
+    state: Mutex<ChunkState>,
+    /// Sends the chunk back to the belt once it's unused.
+    sender: Mutex<mpsc::Sender<Arc<Chunk>>>,
+}
+
+/// Returns the offset at which `size` bytes aligned to `alignment` fit in a chunk of
+/// `chunk_size` bytes, whose free space starts at `offset`.
+fn fit(
+    chunk_size: BufferAddress,
+    offset: BufferAddress,
+    size: BufferAddress,
+    alignment: BufferAddress,
+) -> Option<BufferAddress> {
+    let start = align_up(offset, alignment);
+    if start + size <= chunk_size {
+        Some(start)
+    } else {
+        None
+    }
+}
+
+/// Readback belt is a machine that downloads data.
+///
+/// It is the counterpart of [`StagingBelt`](super::StagingBelt): internally it uses a ring-buffer
+/// of readback buffers that are sub-allocated, so downloading data every frame doesn't require
+/// creating new buffers.
+///
+/// Using a readback belt generally goes as follows:
+/// - Record the copies to read back with `read_buffer` or `read_texture`, keeping the returned handles.
+/// - Submit all command encoders used with `read_buffer` and `read_texture`.
+/// - Call `finish`.
+/// - Wait for the handles with [`ReadbackHandle::mapped`], polling the device on native,
+///   and read their data.
+/// - Drop the handles. Their chunk is reused once all the handles to it are dropped.
+pub struct ReadbackBelt {
+    chunk_size: BufferAddress,
+    /// Chunks that we are actively recording copies into, with the offset of their free space.
+    active_chunks: Vec<(Arc<Chunk>, BufferAddress)>,
+    /// Unused chunks whose mapping is not resolved yet.
+    returned_chunks: Vec<Arc<Chunk>>,
+    /// Chunks that are unmapped and ready to be used.
+    free_chunks: Vec<Arc<Chunk>>,
+    sender: mpsc::Sender<Arc<Chunk>>,
+    receiver: mpsc::Receiver<Arc<Chunk>>,
+}
+
+/// Data read back by a [`ReadbackBelt`].
+///
+/// The chunk of the belt holding the data is reused once all the handles to it are dropped.
+pub struct ReadbackHandle {
+    chunk: Arc<Chunk>,
+    range: Range<BufferAddress>,
+    /// Layout of the rows, for the data of a texture.
+    level: Option<DownloadTextureLevel>,
+}
+
+impl ReadbackHandle {
+    fn new(
+        chunk: Arc<Chunk>,
+        range: Range<BufferAddress>,
+        level: Option<DownloadTextureLevel>,
+    ) -> Self {
+        chunk.state.lock().handles += 1;
+        ReadbackHandle {
+            chunk,
+            range,
+            level,
+        }
+    }
+
+    /// Returns the size of the data, in bytes.
+    ///
+    /// For textures, this includes the padding of the rows, see [`ReadbackHandle::get`].
+    pub fn size(&self) -> BufferAddress {
+        self.range.end - self.range.start
+    }
+
+    /// Returns `true` if the data is available to the host.
+    ///
+    /// The device has to be polled for this to happen on native.
+    pub fn is_ready(&self) -> bool {
+        matches!(self.chunk.state.lock().result, Some(Ok(())))
+    }
+
+    /// Returns `true` if mapping the data failed, in which case it never becomes available.
+    pub fn has_failed(&self) -> bool {
+        matches!(self.chunk.state.lock().result, Some(Err(_)))
+    }
+
+    /// Returns a future that resolves once the data is available, or once mapping it failed.
+    ///
+    /// The mapping starts with [`ReadbackBelt::finish`]. The device has to be polled
+    /// for it to resolve on native.
+    pub fn mapped(&self) -> impl Future<Output = Result<(), BufferAsyncError>> + Send {
+        let (future, completion) = new_gpu_future();
+        let mut state = self.chunk.state.lock();
+        match state.result {
+            Some(ref result) => completion.complete(result.clone()),
+            None => state.waiters.push(completion),
+        }
+        future
+    }
+
+    /// Returns a view of the data, if it is available to the host.
+    ///
+    /// For textures, rows of texel blocks are padded to [`COPY_BYTES_PER_ROW_ALIGNMENT`],
+    /// use [`ReadbackHandle::to_vec`] to get them tightly packed.
+    pub fn get(&self) -> Option<BufferView> {
+        if !self.is_ready() {
+            return None;
+        }
+        self.chunk
+            .buffer
+            .slice(self.range.clone())
+            .try_get_mapped_range()
+            .ok()
+    }
+
+    /// Returns a copy of the data, if it is available to the host.
+    ///
+    /// For textures, rows of texel blocks are tightly packed.
+    pub fn to_vec(&self) -> Option<Vec<u8>> {
+        let view = self.get()?;
+        Some(match self.level {
+            Some(level) => unpad_levels(&view, &[level]),
+            None => view.to_vec(),
+        })
+    }
+}
+
+impl Drop for ReadbackHandle {
+    fn drop(&mut self) {
+        let unused = {
+            let mut state = self.chunk.state.lock();
+            state.handles -= 1;
+            state.is_unused()
+        };
+        if unused {
+            // The belt may be gone, in which case the chunk is simply released
+            let _ = self.chunk.sender.lock().send(Arc::clone(&self.chunk));
+        }
+    }
+}
+
+impl ReadbackBelt {
+    /// Create a new readback belt.
+    ///
+    /// The `chunk_size` is the unit of internal buffer allocation.
+    /// It's better when it's big, but ideally still 1-4 times less than
+    /// the total amount of data read back per submission.
+    pub fn new(chunk_size: BufferAddress) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        ReadbackBelt {
+            chunk_size,
+            active_chunks: Vec::new(),
+            returned_chunks: Vec::new(),
+            free_chunks: Vec::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Allocate `size` bytes at an offset aligned to `alignment`, returning the chunk and offset.
+    fn allocate(
+        &mut self,
+        size: BufferAddress,
+        alignment: BufferAddress,
+        device: &Device,
+    ) -> (Arc<Chunk>, BufferAddress) {
+        self.receive_chunks();
+        let found =
+            self.active_chunks
+                .iter()
+                .enumerate()
+                .find_map(|(index, &(ref chunk, offset))| {
+                    fit(chunk.size, offset, size, alignment).map(|start| (index, start))
+                });
+        let (index, start) = match found {
+            Some(found) => found,
+            None => {
+                let chunk = if let Some(index) =
+                    self.free_chunks.iter().position(|chunk| size <= chunk.size)
+                {
+                    self.free_chunks.swap_remove(index)
+                } else {
+                    let size = self.chunk_size.max(size);
+                    Arc::new(Chunk {
+                        buffer: device.create_buffer(&BufferDescriptor {
+                            label: Some("readback"),
+                            size,
+                            usage: BufferUsage::MAP_READ | BufferUsage::COPY_DST,
+                            mapped_at_creation: false,
+                        }),
+                        size,
+                        state: Mutex::new(ChunkState::default()),
+                        sender: Mutex::new(self.sender.clone()),
+                    })
+                };
+                self.active_chunks.push((chunk, 0));
+                (self.active_chunks.len() - 1, 0)
+            }
+        };
+
+        let (ref chunk, ref mut offset) = self.active_chunks[index];
+        *offset = start + size;
+        (Arc::clone(chunk), start)
+    }
+
+    /// Record a copy of `size` bytes of the `source` buffer, starting at `offset`, to be read back.
+    ///
+    /// The copy will be placed into the provided command encoder. This encoder
+    /// must be submitted before `finish` is called.
+    pub fn read_buffer(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        source: &Buffer,
+        offset: BufferAddress,
+        size: BufferSize,
+        device: &Device,
+    ) -> ReadbackHandle {
+        let (chunk, start) = self.allocate(size.get(), MAP_ALIGNMENT, device);
+        encoder.copy_buffer_to_buffer(source, offset, &chunk.buffer, start, size.get());
+        ReadbackHandle::new(chunk, start..start + size.get(), None)
+    }
+
+    /// Record a copy of the `size` region of the `source` texture, to be read back.
+    ///
+    /// For block-compressed formats, `size` is rounded up to whole blocks. The rows of the
+    /// copy are padded as required, see [`ReadbackHandle::get`] and [`ReadbackHandle::to_vec`].
+    ///
+    /// The copy will be placed into the provided command encoder. This encoder
+    /// must be submitted before `finish` is called.
+    pub fn read_texture(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        source: ImageCopyTexture,
+        size: Extent3d,
+        device: &Device,
+    ) -> ReadbackHandle {
+        let is_3d = source.texture.dimension() == TextureDimension::D3;
+        let (levels, total_size) =
+            download_levels(source.texture.format(), size, is_3d, source.mip_level, 1);
+        let level = levels[0];
+        assert_ne!(total_size, 0, "Texture readbacks can not be empty");
+        let (chunk, start) = self.allocate(
+            total_size,
+            COPY_BYTES_PER_ROW_ALIGNMENT as BufferAddress,
+            device,
+        );
+        encoder.copy_texture_to_buffer(
+            source,
+            ImageCopyBuffer {
+                buffer: &chunk.buffer,
+                layout: ImageDataLayout {
+                    offset: start,
+                    bytes_per_row: NonZeroU32::new(level.padded_bytes_per_row),
+                    rows_per_image: NonZeroU32::new(level.size.height),
+                },
+            },
+            size,
+        );
+        ReadbackHandle::new(chunk, start..start + total_size, Some(level))
+    }
+
+    /// Start mapping the buffers used so far for reading.
+    ///
+    /// This has to be called after the command encoders written to by `read_buffer`
+    /// and `read_texture` are submitted!
+    pub fn finish(&mut self) {
+        for (chunk, _) in self.active_chunks.drain(..) {
+            let unused = {
+                let mut state = chunk.state.lock();
+                state.closed = true;
+                state.is_unused()
+            };
+            if unused {
+                // All the handles are already dropped, there is nothing to map
+                *chunk.state.lock() = ChunkState::default();
+                self.free_chunks.push(chunk);
+                continue;
+            }
+            let mapped_chunk = Arc::clone(&chunk);
+            chunk
+                .buffer
+                .slice(..)
+                .map_async_with(MapMode::Read, move |result| {
+                    let waiters = mapped_chunk.state.lock().resolve(result.clone());
+                    for waiter in waiters {
+                        waiter.complete(result.clone());
+                    }
+                });
+        }
+    }
+
+    /// Move the chunks whose handles are all dropped to the free list, once their
+    /// mapping is resolved.
+    fn receive_chunks(&mut self) {
+        self.returned_chunks.extend(self.receiver.try_iter());
+        let mut index = 0;
+        while index < self.returned_chunks.len() {
+            let result = self.returned_chunks[index].state.lock().result.clone();
+            match result {
+                None => index += 1,
+                Some(result) => {
+                    let chunk = self.returned_chunks.swap_remove(index);
+                    if result.is_ok() {
+                        chunk.buffer.unmap();
+                    }
+                    *chunk.state.lock() = ChunkState::default();
+                    self.free_chunks.push(chunk);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit, ChunkState};
+    use crate::{backend::new_gpu_future, BufferAsyncError};
+
+    #[test]
+    fn fit_aligns() {
+        assert_eq!(fit(256, 0, 16, 8), Some(0));
+        assert_eq!(fit(256, 3, 16, 8), Some(8));
+        assert_eq!(fit(256, 240, 16, 8), Some(240));
+        assert_eq!(fit(256, 241, 16, 8), None);
+        assert_eq!(fit(512, 1, 256, 256), Some(256));
+        assert_eq!(fit(512, 257, 256, 256), None);
+    }
+
+    #[test]
+    fn unused_once_closed_without_handles() {
+        let mut state = ChunkState {
+            handles: 2,
+            ..ChunkState::default()
+        };
+        assert!(!state.is_unused());
+        state.handles -= 1;
+        state.closed = true;
+        assert!(!state.is_unused());
+        state.handles -= 1;
+        assert!(state.is_unused());
+    }
+
+    #[test]
+    fn unused_handles_dropped_before_close() {
+        let mut state = ChunkState {
+            handles: 1,
+            ..ChunkState::default()
+        };
+        state.handles -= 1;
+        assert!(!state.is_unused());
+        state.closed = true;
+        assert!(state.is_unused());
+    }
+
+    #[test]
+    fn resolve_returns_waiters() {
+        let mut state = ChunkState::default();
+        let (_future_a, completion_a) = new_gpu_future();
+        let (_future_b, completion_b) = new_gpu_future();
+        state.waiters.push(completion_a);
+        state.waiters.push(completion_b);
+        assert_eq!(state.resolve(Err(BufferAsyncError)).len(), 2);
+        assert_eq!(state.result, Some(Err(BufferAsyncError)));
+        assert!(state.waiters.is_empty());
+    }
+}
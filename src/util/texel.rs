@@ -0,0 +1,537 @@
+//! Conversion of single texels between their memory representation and `[f32; 4]`.
+//!
+//! Channels that are missing from a format decode to 0, except alpha which decodes to 1.
+//! Integer formats decode to the integer value, normalized formats to their normalized
+//! value, and sRGB formats to linear values.
+//!
+//! Depth formats decode the depth to the first channel. [`TextureFormat::Depth24Plus`]
+//! and [`TextureFormat::Depth24PlusStencil8`] are assumed to be stored as 24 bit normalized
+//! depth in the low bits of 4 bytes, with the stencil, if any, in the high byte and
+//! decoded to the second channel. Their actual layout is not observable through WebGPU.
+//!
+//! All the formats with single texel blocks are supported. Block-compressed formats
+//! (BC, ETC2, EAC and ASTC) are not: [`decode`] returns `None` and [`encode`] returns
+//! `false` for them.
+
+use crate::TextureFormat;
+use std::convert::TryInto;
+
+#[derive(Clone, Copy)]
+enum Component {
+    Unorm8,
+    Snorm8,
+    Uint8,
+    Sint8,
+    Uint16,
+    Sint16,
+    Float16,
+    Uint32,
+    Sint32,
+    Float32,
+}
+
+impl Component {
+    fn size(self) -> usize {
+        match self {
+            Component::Unorm8 | Component::Snorm8 | Component::Uint8 | Component::Sint8 => 1,
+            Component::Uint16 | Component::Sint16 | Component::Float16 => 2,
+            Component::Uint32 | Component::Sint32 | Component::Float32 => 4,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self {
+            Component::Unorm8 => bytes[0] as f32 / 255.0,
+            Component::Snorm8 => (bytes[0] as i8 as f32 / 127.0).max(-1.0),
+            Component::Uint8 => bytes[0] as f32,
+            Component::Sint8 => bytes[0] as i8 as f32,
+            Component::Uint16 => u16::from_le_bytes(bytes[..2].try_into().unwrap()) as f32,
+            Component::Sint16 => i16::from_le_bytes(bytes[..2].try_into().unwrap()) as f32,
+            Component::Float16 => f16_to_f32(u16::from_le_bytes(bytes[..2].try_into().unwrap())),
+            Component::Uint32 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as f32,
+            Component::Sint32 => i32::from_le_bytes(bytes[..4].try_into().unwrap()) as f32,
+            Component::Float32 => f32::from_le_bytes(bytes[..4].try_into().unwrap()),
+        }
+    }
+
+    fn encode(self, value: f32, bytes: &mut [u8]) {
+        match self {
+            Component::Unorm8 => bytes[0] = (value.max(0.0).min(1.0) * 255.0).round() as u8,
+            Component::Snorm8 => {
+                bytes[0] = (value.max(-1.0).min(1.0) * 127.0).round() as i8 as u8;
+            }
+            // Float to integer casts saturate
+            Component::Uint8 => bytes[0] = value.round() as u8,
+            Component::Sint8 => bytes[0] = value.round() as i8 as u8,
+            Component::Uint16 => bytes[..2].copy_from_slice(&(value.round() as u16).to_le_bytes()),
+            Component::Sint16 => bytes[..2].copy_from_slice(&(value.round() as i16).to_le_bytes()),
+            Component::Float16 => bytes[..2].copy_from_slice(&f32_to_f16(value).to_le_bytes()),
+            Component::Uint32 => bytes[..4].copy_from_slice(&(value.round() as u32).to_le_bytes()),
+            Component::Sint32 => bytes[..4].copy_from_slice(&(value.round() as i32).to_le_bytes()),
+            Component::Float32 => bytes[..4].copy_from_slice(&value.to_le_bytes()),
+        }
+    }
+}
+
+enum Layout {
+    /// Channels stored one after the other, in RGBA order unless `bgra` is set.
+    Components {
+        component: Component,
+        count: usize,
+        srgb: bool,
+        bgra: bool,
+    },
+    Rgb10a2Unorm,
+    Rg11b10Float,
+    Depth24Plus,
+    Depth24PlusStencil8,
+}
+
+/// Returns `None` for block-compressed formats only.
+fn layout(format: TextureFormat) -> Option<Layout> {
+    use Component as C;
+    use TextureFormat as Tf;
+
+    if format.describe().block_dimensions != (1, 1) {
+        return None;
+    }
+
+    let (component, count) = match format {
+        Tf::Rgb10a2Unorm => return Some(Layout::Rgb10a2Unorm),
+        Tf::Rg11b10Float => return Some(Layout::Rg11b10Float),
+        Tf::Depth24Plus => return Some(Layout::Depth24Plus),
+        Tf::Depth24PlusStencil8 => return Some(Layout::Depth24PlusStencil8),
+        Tf::R8Unorm => (C::Unorm8, 1),
+        Tf::R8Snorm => (C::Snorm8, 1),
+        Tf::R8Uint => (C::Uint8, 1),
+        Tf::R8Sint => (C::Sint8, 1),
+        Tf::R16Uint => (C::Uint16, 1),
+        Tf::R16Sint => (C::Sint16, 1),
+        Tf::R16Float => (C::Float16, 1),
+        Tf::Rg8Unorm => (C::Unorm8, 2),
+        Tf::Rg8Snorm => (C::Snorm8, 2),
+        Tf::Rg8Uint => (C::Uint8, 2),
+        Tf::Rg8Sint => (C::Sint8, 2),
+        Tf::R32Uint => (C::Uint32, 1),
+        Tf::R32Sint => (C::Sint32, 1),
+        Tf::R32Float | Tf::Depth32Float => (C::Float32, 1),
+        Tf::Rg16Uint => (C::Uint16, 2),
+        Tf::Rg16Sint => (C::Sint16, 2),
+        Tf::Rg16Float => (C::Float16, 2),
+        Tf::Rgba8Unorm | Tf::Rgba8UnormSrgb | Tf::Bgra8Unorm | Tf::Bgra8UnormSrgb => (C::Unorm8, 4),
+        Tf::Rgba8Snorm => (C::Snorm8, 4),
+        Tf::Rgba8Uint => (C::Uint8, 4),
+        Tf::Rgba8Sint => (C::Sint8, 4),
+        Tf::Rg32Uint => (C::Uint32, 2),
+        Tf::Rg32Sint => (C::Sint32, 2),
+        Tf::Rg32Float => (C::Float32, 2),
+        Tf::Rgba16Uint => (C::Uint16, 4),
+        Tf::Rgba16Sint => (C::Sint16, 4),
+        Tf::Rgba16Float => (C::Float16, 4),
+        Tf::Rgba32Uint => (C::Uint32, 4),
+        Tf::Rgba32Sint => (C::Sint32, 4),
+        Tf::Rgba32Float => (C::Float32, 4),
+        Tf::Bc1RgbaUnorm
+        | Tf::Bc1RgbaUnormSrgb
+        | Tf::Bc2RgbaUnorm
+        | Tf::Bc2RgbaUnormSrgb
+        | Tf::Bc3RgbaUnorm
+        | Tf::Bc3RgbaUnormSrgb
+        | Tf::Bc4RUnorm
+        | Tf::Bc4RSnorm
+        | Tf::Bc5RgUnorm
+        | Tf::Bc5RgSnorm
+        | Tf::Bc6hRgbUfloat
+        | Tf::Bc6hRgbSfloat
+        | Tf::Bc7RgbaUnorm
+        | Tf::Bc7RgbaUnormSrgb
+        | Tf::Etc2RgbUnorm
+        | Tf::Etc2RgbUnormSrgb
+        | Tf::Etc2RgbA1Unorm
+        | Tf::Etc2RgbA1UnormSrgb
+        | Tf::Etc2RgbA8Unorm
+        | Tf::Etc2RgbA8UnormSrgb
+        | Tf::EacRUnorm
+        | Tf::EacRSnorm
+        | Tf::EtcRgUnorm
+        | Tf::EtcRgSnorm
+        | Tf::Astc4x4RgbaUnorm
+        | Tf::Astc4x4RgbaUnormSrgb
+        | Tf::Astc5x4RgbaUnorm
+        | Tf::Astc5x4RgbaUnormSrgb
+        | Tf::Astc5x5RgbaUnorm
+        | Tf::Astc5x5RgbaUnormSrgb
+        | Tf::Astc6x5RgbaUnorm
+        | Tf::Astc6x5RgbaUnormSrgb
+        | Tf::Astc6x6RgbaUnorm
+        | Tf::Astc6x6RgbaUnormSrgb
+        | Tf::Astc8x5RgbaUnorm
+        | Tf::Astc8x5RgbaUnormSrgb
+        | Tf::Astc8x6RgbaUnorm
+        | Tf::Astc8x6RgbaUnormSrgb
+        | Tf::Astc10x5RgbaUnorm
+        | Tf::Astc10x5RgbaUnormSrgb
+        | Tf::Astc10x6RgbaUnorm
+        | Tf::Astc10x6RgbaUnormSrgb
+        | Tf::Astc8x8RgbaUnorm
+        | Tf::Astc8x8RgbaUnormSrgb
+        | Tf::Astc10x8RgbaUnorm
+        | Tf::Astc10x8RgbaUnormSrgb
+        | Tf::Astc10x10RgbaUnorm
+        | Tf::Astc10x10RgbaUnormSrgb
+        | Tf::Astc12x10RgbaUnorm
+        | Tf::Astc12x10RgbaUnormSrgb
+        | Tf::Astc12x12RgbaUnorm
+        | Tf::Astc12x12RgbaUnormSrgb => unreachable!("{:?} is block-compressed", format),
+    };
+    Some(Layout::Components {
+        component,
+        count,
+        srgb: matches!(format, Tf::Rgba8UnormSrgb | Tf::Bgra8UnormSrgb),
+        bgra: matches!(format, Tf::Bgra8Unorm | Tf::Bgra8UnormSrgb),
+    })
+}
+
+/// Returns the size of a texel of `format` in bytes, or `None` if it's block-compressed.
+pub fn texel_size(format: TextureFormat) -> Option<usize> {
+    let info = format.describe();
+    if info.block_dimensions == (1, 1) {
+        Some(info.block_size as usize)
+    } else {
+        None
+    }
+}
+
+/// Decode the texel of `format` at the start of `bytes`.
+///
+/// Returns `None` if the format is block-compressed.
+///
+/// # Panics
+///
+/// Panics if `bytes` is shorter than the texel size.
+pub fn decode(format: TextureFormat, bytes: &[u8]) -> Option<[f32; 4]> {
+    let size = texel_size(format)?;
+    assert!(
+        bytes.len() >= size,
+        "{} bytes are needed to decode a texel of {:?}",
+        size,
+        format
+    );
+    let word = || u32::from_le_bytes(bytes[..4].try_into().unwrap());
+
+    Some(match layout(format)? {
+        Layout::Components {
+            component,
+            count,
+            srgb,
+            bgra,
+        } => {
+            let mut texel = [0.0, 0.0, 0.0, 1.0];
+            for (index, channel) in texel.iter_mut().take(count).enumerate() {
+                *channel = component.decode(&bytes[index * component.size()..]);
+            }
+            if bgra {
+                texel.swap(0, 2);
+            }
+            if srgb {
+                for channel in texel.iter_mut().take(3) {
+                    *channel = srgb_to_linear(*channel);
+                }
+            }
+            texel
+        }
+        Layout::Rgb10a2Unorm => {
+            let word = word();
+            [
+                (word & 0x3ff) as f32 / 1023.0,
+                ((word >> 10) & 0x3ff) as f32 / 1023.0,
+                ((word >> 20) & 0x3ff) as f32 / 1023.0,
+                (word >> 30) as f32 / 3.0,
+            ]
+        }
+        Layout::Rg11b10Float => {
+            let word = word();
+            [
+                decode_small_float((word >> 6) & 0x1f, word & 0x3f, 6),
+                decode_small_float((word >> 17) & 0x1f, (word >> 11) & 0x3f, 6),
+                decode_small_float(word >> 27, (word >> 22) & 0x1f, 5),
+                1.0,
+            ]
+        }
+        Layout::Depth24Plus => [
+            (word() & 0xff_ffff) as f32 / 0xff_ffff as f32,
+            0.0,
+            0.0,
+            1.0,
+        ],
+        Layout::Depth24PlusStencil8 => {
+            let word = word();
+            [
+                (word & 0xff_ffff) as f32 / 0xff_ffff as f32,
+                (word >> 24) as f32,
+                0.0,
+                1.0,
+            ]
+        }
+    })
+}
+
+/// Encode `texel` as a texel of `format`, at the start of `bytes`.
+///
+/// Values out of the range of the format are clamped.
+/// Returns `false`, leaving `bytes` untouched, if the format is block-compressed.
+///
+/// # Panics
+///
+/// Panics if `bytes` is shorter than the texel size.
+pub fn encode(format: TextureFormat, texel: [f32; 4], bytes: &mut [u8]) -> bool {
+    let size = match texel_size(format) {
+        Some(size) => size,
+        None => return false,
+    };
+    assert!(
+        bytes.len() >= size,
+        "{} bytes are needed to encode a texel of {:?}",
+        size,
+        format
+    );
+    let unorm = |value: f32, max: u32| (value.max(0.0).min(1.0) * max as f32).round() as u32;
+
+    let word = match layout(format) {
+        None => return false,
+        Some(Layout::Components {
+            component,
+            count,
+            srgb,
+            bgra,
+        }) => {
+            let mut texel = texel;
+            if srgb {
+                for channel in texel.iter_mut().take(3) {
+                    *channel = linear_to_srgb(*channel);
+                }
+            }
+            if bgra {
+                texel.swap(0, 2);
+            }
+            for (index, &channel) in texel.iter().take(count).enumerate() {
+                component.encode(channel, &mut bytes[index * component.size()..]);
+            }
+            return true;
+        }
+        Some(Layout::Rgb10a2Unorm) => {
+            unorm(texel[0], 0x3ff)
+                | unorm(texel[1], 0x3ff) << 10
+                | unorm(texel[2], 0x3ff) << 20
+                | unorm(texel[3], 3) << 30
+        }
+        Some(Layout::Rg11b10Float) => {
+            let (r_exponent, r_mantissa) = encode_small_float(texel[0], 6);
+            let (g_exponent, g_mantissa) = encode_small_float(texel[1], 6);
+            let (b_exponent, b_mantissa) = encode_small_float(texel[2], 5);
+            r_mantissa
+                | r_exponent << 6
+                | g_mantissa << 11
+                | g_exponent << 17
+                | b_mantissa << 22
+                | b_exponent << 27
+        }
+        Some(Layout::Depth24Plus) => unorm(texel[0], 0xff_ffff),
+        Some(Layout::Depth24PlusStencil8) => {
+            unorm(texel[0], 0xff_ffff) | (texel[1].round() as u8 as u32) << 24
+        }
+    };
+    bytes[..4].copy_from_slice(&word.to_le_bytes());
+    true
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Decode a float with a 5 bit exponent and no sign, as used by half and packed floats.
+fn decode_small_float(exponent: u32, mantissa: u32, mantissa_bits: u32) -> f32 {
+    let scale = (1u32 << mantissa_bits) as f32;
+    match exponent {
+        0 => mantissa as f32 / scale * 2f32.powi(-14),
+        31 if mantissa == 0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa as f32 / scale) * 2f32.powi(exponent as i32 - 15),
+    }
+}
+
+/// Encode a float with a 5 bit exponent and no sign, returning the exponent and mantissa.
+///
+/// Negative values are clamped to zero, and the mantissa is rounded to nearest.
+fn encode_small_float(value: f32, mantissa_bits: u32) -> (u32, u32) {
+    let scale = (1u32 << mantissa_bits) as f32;
+    if value.is_nan() {
+        return (31, 1);
+    }
+    if value <= 0.0 {
+        return (0, 0);
+    }
+    if value < 2f32.powi(-14) {
+        let mantissa = (value * 2f32.powi(14) * scale).round() as u32;
+        return if mantissa == 1 << mantissa_bits {
+            (1, 0)
+        } else {
+            (0, mantissa)
+        };
+    }
+    if value.is_infinite() {
+        return (31, 0);
+    }
+    let mut exponent = ((value.to_bits() >> 23) & 0xff) as i32 - 127;
+    let mut mantissa = ((value / 2f32.powi(exponent) - 1.0) * scale).round() as u32;
+    if mantissa == 1 << mantissa_bits {
+        exponent += 1;
+        mantissa = 0;
+    }
+    if exponent + 15 >= 31 {
+        return (31, 0);
+    }
+    ((exponent + 15) as u32, mantissa)
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let value = decode_small_float((half as u32 >> 10) & 0x1f, half as u32 & 0x3ff, 10);
+    if half & 0x8000 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+fn f32_to_f16(value: f32) -> u16 {
+    let sign = if value.is_sign_negative() { 0x8000 } else { 0 };
+    let (exponent, mantissa) = encode_small_float(value.abs(), 10);
+    sign | (exponent << 10 | mantissa) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, f16_to_f32, f32_to_f16, layout, texel_size};
+    use crate::TextureFormat;
+
+    /// Every format with single texel blocks, and formats of each block-compressed family.
+    const FORMATS: &[TextureFormat] = &[
+        TextureFormat::R8Unorm,
+        TextureFormat::R8Snorm,
+        TextureFormat::R8Uint,
+        TextureFormat::R8Sint,
+        TextureFormat::R16Uint,
+        TextureFormat::R16Sint,
+        TextureFormat::R16Float,
+        TextureFormat::Rg8Unorm,
+        TextureFormat::Rg8Snorm,
+        TextureFormat::Rg8Uint,
+        TextureFormat::Rg8Sint,
+        TextureFormat::R32Uint,
+        TextureFormat::R32Sint,
+        TextureFormat::R32Float,
+        TextureFormat::Rg16Uint,
+        TextureFormat::Rg16Sint,
+        TextureFormat::Rg16Float,
+        TextureFormat::Rgba8Unorm,
+        TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Rgba8Snorm,
+        TextureFormat::Rgba8Uint,
+        TextureFormat::Rgba8Sint,
+        TextureFormat::Bgra8Unorm,
+        TextureFormat::Bgra8UnormSrgb,
+        TextureFormat::Rgb10a2Unorm,
+        TextureFormat::Rg11b10Float,
+        TextureFormat::Rg32Uint,
+        TextureFormat::Rg32Sint,
+        TextureFormat::Rg32Float,
+        TextureFormat::Rgba16Uint,
+        TextureFormat::Rgba16Sint,
+        TextureFormat::Rgba16Float,
+        TextureFormat::Rgba32Uint,
+        TextureFormat::Rgba32Sint,
+        TextureFormat::Rgba32Float,
+        TextureFormat::Depth32Float,
+        TextureFormat::Depth24Plus,
+        TextureFormat::Depth24PlusStencil8,
+        TextureFormat::Bc1RgbaUnorm,
+        TextureFormat::Bc1RgbaUnormSrgb,
+        TextureFormat::Etc2RgbUnormSrgb,
+        TextureFormat::Astc4x4RgbaUnormSrgb,
+    ];
+
+    #[test]
+    fn only_compressed_formats_are_unsupported() {
+        for &format in FORMATS {
+            let compressed = format.describe().block_dimensions != (1, 1);
+            assert_eq!(texel_size(format).is_none(), compressed, "{:?}", format);
+            assert_eq!(layout(format).is_none(), compressed, "{:?}", format);
+
+            let mut bytes = [0u8; 16];
+            assert_eq!(
+                encode(format, [0.0, 0.0, 0.0, 1.0], &mut bytes),
+                !compressed,
+                "{:?}",
+                format
+            );
+            assert_eq!(decode(format, &bytes).is_none(), compressed, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn half_float_conversion() {
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(1.0e6), 0x7c00);
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001);
+        assert_eq!(f16_to_f32(0x3555), 0.333_251_95);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+    }
+
+    #[test]
+    fn round_trip() {
+        let cases = [
+            (TextureFormat::Rgba8Unorm, [0.0, 1.0, 0.2, 1.0]),
+            (TextureFormat::Bgra8UnormSrgb, [0.5, 0.25, 1.0, 0.0]),
+            (TextureFormat::Rg8Sint, [-12.0, 100.0, 0.0, 1.0]),
+            (TextureFormat::Rgba16Float, [0.5, -3.0, 1024.0, 0.125]),
+            (TextureFormat::Rgb10a2Unorm, [1.0, 0.0, 0.5, 1.0]),
+            (TextureFormat::Rg11b10Float, [1.5, 0.0, 4.0, 1.0]),
+            (TextureFormat::Depth32Float, [0.75, 0.0, 0.0, 1.0]),
+            (TextureFormat::Depth24PlusStencil8, [1.0, 5.0, 0.0, 1.0]),
+        ];
+        for &(format, texel) in cases.iter() {
+            let mut bytes = [0u8; 16];
+            assert!(encode(format, texel, &mut bytes));
+            let decoded = decode(format, &bytes).unwrap();
+            for (a, b) in texel.iter().zip(decoded.iter()) {
+                assert!(
+                    (a - b).abs() < 0.005,
+                    "{:?}: {:?} != {:?}",
+                    format,
+                    texel,
+                    decoded
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn compressed_formats_are_not_supported() {
+        assert_eq!(decode(TextureFormat::Bc1RgbaUnorm, &[0; 8]), None);
+        assert!(!encode(TextureFormat::Bc1RgbaUnorm, [0.0; 4], &mut [0; 8]));
+    }
+}
@@ -0,0 +1,221 @@
+use super::{element_size, BufferInitDescriptor, DeviceExt};
+use crate::{
+    BindingResource, Buffer, BufferAddress, BufferAsyncError, BufferDescriptor, BufferSlice,
+    BufferUsage, Device, Label, MapMode, Queue, MAP_ALIGNMENT,
+};
+use std::{
+    future::Future,
+    marker::PhantomData,
+    ops::{Bound, Range, RangeBounds},
+};
+
+/// A [`Buffer`] holding an array of `T`, addressed in elements rather than bytes.
+///
+/// The size of `T` has to be a multiple of [`COPY_BUFFER_ALIGNMENT`](crate::COPY_BUFFER_ALIGNMENT), so that any
+/// element range can be copied or written to. Mapping additionally requires the
+/// start of the range to be a multiple of [`MAP_ALIGNMENT`] bytes, which is always
+/// the case if the size of `T` is a multiple of it.
+#[derive(Debug)]
+pub struct TypedBuffer<T> {
+    buffer: Buffer,
+    len: BufferAddress,
+    _marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> TypedBuffer<T> {
+    /// Create a buffer able to hold `len` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of `T` is not a multiple of [`COPY_BUFFER_ALIGNMENT`](crate::COPY_BUFFER_ALIGNMENT).
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn new(device: &Device, label: Label, len: BufferAddress, usage: BufferUsage) -> Self {
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label,
+            size: len * element_size::<T>(),
+            usage,
+            mapped_at_creation: false,
+        });
+        TypedBuffer {
+            buffer,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a buffer initialized with `contents`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of `T` is not a multiple of [`COPY_BUFFER_ALIGNMENT`](crate::COPY_BUFFER_ALIGNMENT).
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn with_contents(
+        device: &Device,
+        label: Label,
+        contents: &[T],
+        usage: BufferUsage,
+    ) -> Self {
+        element_size::<T>();
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label,
+            contents: bytemuck::cast_slice(contents),
+            usage,
+        });
+        TypedBuffer {
+            buffer,
+            len: contents.len() as BufferAddress,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the underlying untyped buffer.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the number of elements the buffer can hold.
+    pub fn len(&self) -> BufferAddress {
+        self.len
+    }
+
+    /// Returns `true` if the buffer holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the binding view of the entire buffer.
+    pub fn as_entire_binding(&self) -> BindingResource {
+        self.buffer.as_entire_binding()
+    }
+
+    /// Use only a portion of this buffer, given in elements.
+    ///
+    /// The returned slice can be mapped and viewed with [`BufferView::as_typed`](crate::BufferView::as_typed).
+    pub fn slice<S: RangeBounds<BufferAddress>>(&self, bounds: S) -> BufferSlice {
+        self.buffer.slice(byte_range::<T, _>(bounds, self.len))
+    }
+
+    /// Map a range of elements of the buffer.
+    ///
+    /// See [`BufferSlice::map_async`] for the details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the first element of the range doesn't start at a multiple of [`MAP_ALIGNMENT`] bytes.
+    pub fn map_async<S: RangeBounds<BufferAddress>>(
+        &self,
+        bounds: S,
+        mode: MapMode,
+    ) -> impl Future<Output = Result<(), BufferAsyncError>> + Send {
+        let range = map_byte_range::<T, _>(bounds, self.len);
+        self.buffer.slice(range).map_async(mode)
+    }
+
+    /// Schedule writing `data` into the buffer, starting at element `index`.
+    ///
+    /// See [`Queue::write_buffer`] for the details.
+    pub fn write_buffer(&self, queue: &Queue, index: BufferAddress, data: &[T]) {
+        if data.is_empty() {
+            return;
+        }
+        let range = byte_range::<T, _>(index..index + data.len() as BufferAddress, self.len);
+        queue.write_buffer(&self.buffer, range.start, bytemuck::cast_slice(data));
+    }
+}
+
+/// Converts a range of elements of `T`, in a buffer of `len` elements, into a range of bytes.
+///
+/// # Panics
+///
+/// Panics if the range is empty or out of bounds, or if the size of `T` is not a multiple
+/// of [`COPY_BUFFER_ALIGNMENT`](crate::COPY_BUFFER_ALIGNMENT).
+fn byte_range<T, S: RangeBounds<BufferAddress>>(
+    bounds: S,
+    len: BufferAddress,
+) -> Range<BufferAddress> {
+    let start = match bounds.start_bound() {
+        Bound::Included(&bound) => bound,
+        Bound::Excluded(&bound) => bound + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match bounds.end_bound() {
+        Bound::Included(&bound) => bound + 1,
+        Bound::Excluded(&bound) => bound,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start < end && end <= len,
+        "Element range {:?} is empty or out of bounds for a buffer of {} elements",
+        start..end,
+        len
+    );
+    let size = element_size::<T>();
+    start * size..end * size
+}
+
+/// Same as [`byte_range`], additionally checking that the range can be mapped.
+fn map_byte_range<T, S: RangeBounds<BufferAddress>>(
+    bounds: S,
+    len: BufferAddress,
+) -> Range<BufferAddress> {
+    let range = byte_range::<T, _>(bounds, len);
+    assert_eq!(
+        range.start % MAP_ALIGNMENT,
+        0,
+        "Mapping must start at a multiple of MAP_ALIGNMENT bytes"
+    );
+    range
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{byte_range, map_byte_range};
+
+    #[test]
+    fn element_to_byte_range() {
+        assert_eq!(byte_range::<u32, _>(.., 10), 0..40);
+        assert_eq!(byte_range::<u32, _>(2..5, 10), 8..20);
+        assert_eq!(byte_range::<u32, _>(2..=5, 10), 8..24);
+        assert_eq!(byte_range::<u32, _>(7.., 10), 28..40);
+        assert_eq!(byte_range::<u32, _>(..3, 10), 0..12);
+        assert_eq!(byte_range::<[f32; 4], _>(1..3, 4), 16..48);
+        assert_eq!(byte_range::<[u16; 2], _>(9..10, 10), 36..40);
+    }
+
+    #[test]
+    #[should_panic(expected = "is empty or out of bounds")]
+    fn empty_range() {
+        byte_range::<u32, _>(3..3, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "is empty or out of bounds")]
+    fn out_of_bounds_range() {
+        byte_range::<u32, _>(8..11, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "is empty or out of bounds")]
+    fn empty_buffer() {
+        byte_range::<u32, _>(.., 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a multiple of COPY_BUFFER_ALIGNMENT")]
+    fn unaligned_element() {
+        byte_range::<[u8; 6], _>(.., 10);
+    }
+
+    #[test]
+    fn map_range() {
+        // MAP_ALIGNMENT is 8 bytes
+        assert_eq!(map_byte_range::<u32, _>(2..5, 10), 8..20);
+        assert_eq!(map_byte_range::<[u32; 2], _>(3.., 10), 24..80);
+    }
+
+    #[test]
+    #[should_panic(expected = "Mapping must start at a multiple of MAP_ALIGNMENT bytes")]
+    fn unaligned_map_range() {
+        map_byte_range::<u32, _>(1..5, 10);
+    }
+}
@@ -0,0 +1,243 @@
+use super::{align_up, StagingBelt};
+use crate::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry,
+    BindingResource, BindingType, Buffer, BufferAddress, BufferBinding, BufferBindingType,
+    BufferDescriptor, BufferSize, BufferUsage, CommandEncoder, Device, DynamicOffset, Queue,
+    ShaderStage, BIND_BUFFER_ALIGNMENT,
+};
+use std::{marker::PhantomData, mem::size_of};
+
+/// Uniform buffer holding one `T` per draw, bound with dynamic offsets.
+///
+/// Values are pushed on the CPU side every frame, and uploaded at once by `flush`.
+/// Each value is placed at a multiple of the alignment required for dynamic offsets
+/// of uniform buffers, see [`DynamicUniformBuffer::new`].
+///
+/// Using a dynamic uniform buffer generally goes as follows:
+/// - Create the bind group layout with `binding_layout_entry`, and the bind group with `create_bind_group`.
+/// - Call `clear`, then `push` the values of the frame, keeping the returned offsets.
+/// - Call `flush`, or `flush_with_belt`, and recreate the bind group if it returns `true`.
+/// - Pass the offsets to `set_bind_group` when drawing.
+pub struct DynamicUniformBuffer<T> {
+    buffer: Buffer,
+    /// Number of values the buffer can hold.
+    capacity: BufferAddress,
+    data: UniformData,
+    _marker: PhantomData<T>,
+}
+
+/// Values pushed since the last `clear`, each padded to the stride.
+struct UniformData {
+    stride: BufferAddress,
+    bytes: Vec<u8>,
+}
+
+impl UniformData {
+    fn new(size: BufferAddress, min_offset_alignment: BufferAddress) -> Self {
+        UniformData {
+            stride: align_up(size, BIND_BUFFER_ALIGNMENT.max(min_offset_alignment)),
+            bytes: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.bytes.len() / self.stride as usize
+    }
+
+    fn push(&mut self, value: &[u8]) -> DynamicOffset {
+        let offset = self.bytes.len();
+        self.bytes.extend_from_slice(value);
+        self.bytes.resize(offset + self.stride as usize, 0);
+        offset as DynamicOffset
+    }
+}
+
+impl<T: bytemuck::Pod> DynamicUniformBuffer<T> {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn create_buffer(device: &Device, capacity: BufferAddress, stride: BufferAddress) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("dynamic uniforms"),
+            size: capacity * stride,
+            usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Create a buffer able to hold `capacity` values before growing.
+    ///
+    /// Values are placed at multiples of the largest of `min_offset_alignment` and
+    /// [`BIND_BUFFER_ALIGNMENT`]. `Limits` doesn't report the offset alignment of uniform
+    /// buffers yet, so pass the device's `minUniformBufferOffsetAlignment` here if it's
+    /// known to be larger, or 0.
+    ///
+    /// # Panics
+    ///
+    /// - If `T` is zero sized.
+    /// - If `min_offset_alignment` is neither 0 nor a power of two.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn new(
+        device: &Device,
+        capacity: BufferAddress,
+        min_offset_alignment: BufferAddress,
+    ) -> Self {
+        assert_ne!(size_of::<T>(), 0, "Uniform values can not be zero sized");
+        assert!(
+            min_offset_alignment == 0 || min_offset_alignment.is_power_of_two(),
+            "Offset alignment must be a power of two"
+        );
+        let capacity = capacity.max(1);
+        let data = UniformData::new(size_of::<T>() as BufferAddress, min_offset_alignment);
+        DynamicUniformBuffer {
+            buffer: Self::create_buffer(device, capacity, data.stride),
+            capacity,
+            data,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the distance between two consecutive values in the buffer.
+    pub fn stride(&self) -> BufferAddress {
+        self.data.stride
+    }
+
+    /// Returns the underlying buffer.
+    ///
+    /// It's replaced when the buffer grows, see `flush`.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the number of values pushed since the last `clear`.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if no value was pushed since the last `clear`.
+    pub fn is_empty(&self) -> bool {
+        self.data.bytes.is_empty()
+    }
+
+    /// Remove all the values, to start a new frame.
+    pub fn clear(&mut self) {
+        self.data.bytes.clear();
+    }
+
+    /// Add a value, returning the dynamic offset to bind it with.
+    pub fn push(&mut self, value: &T) -> DynamicOffset {
+        self.data.push(bytemuck::bytes_of(value))
+    }
+
+    /// Make room in the buffer for all the values, returning `true` if it was replaced.
+    fn reserve(&mut self, device: &Device) -> bool {
+        let len = self.len() as BufferAddress;
+        if len <= self.capacity {
+            return false;
+        }
+        self.capacity = len.next_power_of_two();
+        self.buffer = Self::create_buffer(device, self.capacity, self.data.stride);
+        true
+    }
+
+    /// Schedule uploading the values with [`Queue::write_buffer`].
+    ///
+    /// Returns `true` if the buffer had to grow, in which case the bind groups
+    /// using it must be recreated.
+    pub fn flush(&mut self, device: &Device, queue: &Queue) -> bool {
+        let replaced = self.reserve(device);
+        if !self.data.bytes.is_empty() {
+            queue.write_buffer(&self.buffer, 0, &self.data.bytes);
+        }
+        replaced
+    }
+
+    /// Upload the values through a [`StagingBelt`], recording the copy into `encoder`.
+    ///
+    /// Returns `true` if the buffer had to grow, in which case the bind groups
+    /// using it must be recreated.
+    pub fn flush_with_belt(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        belt: &mut StagingBelt,
+    ) -> bool {
+        let replaced = self.reserve(device);
+        if let Some(size) = BufferSize::new(self.data.bytes.len() as BufferAddress) {
+            belt.write_buffer(encoder, &self.buffer, 0, size, device)
+                .copy_from_slice(&self.data.bytes);
+        }
+        replaced
+    }
+
+    /// Returns the bind group layout entry matching the bind groups of this buffer.
+    pub fn binding_layout_entry(binding: u32, visibility: ShaderStage) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: BufferSize::new(size_of::<T>() as BufferAddress),
+            },
+            count: None,
+        }
+    }
+
+    /// Create a bind group with a single entry at `binding`, bound to a value of this buffer.
+    ///
+    /// `layout` is expected to contain [`DynamicUniformBuffer::binding_layout_entry`].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        layout: &BindGroupLayout,
+        binding: u32,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("dynamic uniforms"),
+            layout,
+            entries: &[BindGroupEntry {
+                binding,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &self.buffer,
+                    offset: 0,
+                    size: BufferSize::new(size_of::<T>() as BufferAddress),
+                }),
+            }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UniformData;
+    use crate::BIND_BUFFER_ALIGNMENT;
+
+    #[test]
+    fn stride() {
+        assert_eq!(UniformData::new(4, 0).stride, BIND_BUFFER_ALIGNMENT);
+        assert_eq!(
+            UniformData::new(BIND_BUFFER_ALIGNMENT, 0).stride,
+            BIND_BUFFER_ALIGNMENT
+        );
+        assert_eq!(
+            UniformData::new(BIND_BUFFER_ALIGNMENT + 4, 0).stride,
+            2 * BIND_BUFFER_ALIGNMENT
+        );
+        assert_eq!(UniformData::new(4, 1024).stride, 1024);
+        // A smaller alignment doesn't lower the required one
+        assert_eq!(UniformData::new(4, 16).stride, BIND_BUFFER_ALIGNMENT);
+    }
+
+    #[test]
+    fn push_offsets() {
+        let mut data = UniformData::new(8, 512);
+        assert_eq!(data.len(), 0);
+        assert_eq!(data.push(&[1; 8]), 0);
+        assert_eq!(data.push(&[2; 8]), 512);
+        assert_eq!(data.push(&[3; 8]), 1024);
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.bytes.len(), 3 * 512);
+        assert_eq!(data.bytes[512..520], [2; 8]);
+        assert!(data.bytes[520..1024].iter().all(|&byte| byte == 0));
+    }
+}
@@ -0,0 +1,251 @@
+use super::element_size;
+use crate::{
+    Buffer, BufferAddress, BufferDescriptor, BufferSlice, BufferUsage, CommandEncoder, Device,
+    Queue,
+};
+use std::ops::Range;
+
+/// Growable array of `T` with a copy on the GPU.
+///
+/// Elements are modified on the CPU side, and only the modified ranges are uploaded by `flush`.
+/// When the elements don't fit in the buffer anymore, `flush` replaces it with a larger one,
+/// copying the existing contents on the GPU.
+///
+/// The size of `T` has to be a multiple of [`COPY_BUFFER_ALIGNMENT`](crate::COPY_BUFFER_ALIGNMENT).
+pub struct GpuVec<T> {
+    buffer: Buffer,
+    usage: BufferUsage,
+    /// Number of elements the buffer can hold.
+    capacity: usize,
+    /// Number of elements that are valid in the buffer.
+    gpu_len: usize,
+    data: Vec<T>,
+    /// Ranges of elements modified since the last `flush`.
+    dirty: Vec<Range<usize>>,
+}
+
+impl<T: bytemuck::Pod> GpuVec<T> {
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn create_buffer(device: &Device, usage: BufferUsage, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("gpu vec"),
+            size: capacity as BufferAddress * element_size::<T>(),
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Create an empty vector, with a buffer able to hold `capacity` elements.
+    ///
+    /// [`BufferUsage::COPY_SRC`] and [`BufferUsage::COPY_DST`] are always added to `usage`,
+    /// for uploading and growing the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of `T` is not a multiple of [`COPY_BUFFER_ALIGNMENT`](crate::COPY_BUFFER_ALIGNMENT).
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn new(device: &Device, usage: BufferUsage, capacity: usize) -> Self {
+        let usage = usage | BufferUsage::COPY_SRC | BufferUsage::COPY_DST;
+        let capacity = capacity.max(1);
+        GpuVec {
+            buffer: Self::create_buffer(device, usage, capacity),
+            usage,
+            capacity,
+            gpu_len: 0,
+            data: Vec::new(),
+            dirty: Vec::new(),
+        }
+    }
+
+    /// Returns the buffer holding the elements.
+    ///
+    /// It's replaced when the vector grows, see `flush`.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the slice of the buffer holding the elements, as of the last `flush`.
+    ///
+    /// Returns `None` if there were no elements, as buffer slices can't be empty.
+    pub fn slice(&self) -> Option<BufferSlice> {
+        if self.gpu_len == 0 {
+            return None;
+        }
+        let size = self.gpu_len as BufferAddress * element_size::<T>();
+        Some(self.buffer.slice(..size))
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the number of elements the buffer can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the elements, as seen from the CPU.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Append an element.
+    pub fn push(&mut self, value: T) {
+        self.data.push(value);
+        self.dirty.push(self.data.len() - 1..self.data.len());
+    }
+
+    /// Append all the elements of `values`.
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        let start = self.data.len();
+        self.data.extend(values);
+        self.dirty.push(start..self.data.len());
+    }
+
+    /// Shorten the vector to `len` elements.
+    pub fn truncate(&mut self, len: usize) {
+        self.data.truncate(len);
+        self.gpu_len = self.gpu_len.min(len);
+    }
+
+    /// Remove all the elements.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Replace the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        self.data[index] = value;
+        self.dirty.push(index..index + 1);
+    }
+
+    /// Sorted, merged and clamped ranges of modified elements, clearing them.
+    fn take_dirty(&mut self) -> Vec<Range<usize>> {
+        merge_ranges(std::mem::take(&mut self.dirty), self.data.len())
+    }
+
+    fn write(&self, queue: &Queue, buffer: &Buffer, range: Range<usize>) {
+        if range.start < range.end {
+            let offset = range.start as BufferAddress * element_size::<T>();
+            queue.write_buffer(buffer, offset, bytemuck::cast_slice(&self.data[range]));
+        }
+    }
+
+    /// Schedule uploading the modified elements.
+    ///
+    /// If the buffer is too small, it's replaced by a larger one, and the copy of the existing
+    /// contents is recorded into `encoder`, which must be submitted before the buffer is used.
+    /// Returns `true` in this case, meaning that the bind groups using it must be recreated.
+    pub fn flush(&mut self, device: &Device, queue: &Queue, encoder: &mut CommandEncoder) -> bool {
+        let dirty = self.take_dirty();
+        let len = self.data.len();
+
+        if len <= self.capacity {
+            for range in dirty {
+                self.write(queue, &self.buffer, range);
+            }
+            self.gpu_len = len;
+            return false;
+        }
+
+        let capacity = len.next_power_of_two();
+        let buffer = Self::create_buffer(device, self.usage, capacity);
+        // Writes are executed before the commands of the next submission, so the elements
+        // that will be copied are written into the old buffer, and the others into the new one.
+        let kept = self.gpu_len;
+        for range in dirty {
+            let (old, new) = split_range(range, kept);
+            self.write(queue, &self.buffer, old);
+            self.write(queue, &buffer, new);
+        }
+        if kept != 0 {
+            let size = kept as BufferAddress * element_size::<T>();
+            encoder.copy_buffer_to_buffer(&self.buffer, 0, &buffer, 0, size);
+        }
+
+        self.buffer = buffer;
+        self.capacity = capacity;
+        self.gpu_len = len;
+        true
+    }
+}
+
+/// Sorts and merges overlapping or adjacent `ranges`, clamping them to `len` and
+/// dropping the empty ones.
+fn merge_ranges(mut ranges: Vec<Range<usize>>, len: usize) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        let range = range.start..range.end.min(len);
+        if range.start >= range.end {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Splits `range` into the part below `kept`, and the part above it.
+/// Either part may be empty.
+fn split_range(range: Range<usize>, kept: usize) -> (Range<usize>, Range<usize>) {
+    (
+        range.start.min(kept)..range.end.min(kept),
+        range.start.max(kept)..range.end.max(kept),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_ranges, split_range};
+
+    #[test]
+    fn merge_sorts_and_merges() {
+        assert_eq!(
+            merge_ranges(vec![5..6, 0..2, 1..3, 3..4, 8..9], 10),
+            vec![0..4, 5..6, 8..9]
+        );
+    }
+
+    #[test]
+    fn merge_nested() {
+        assert_eq!(merge_ranges(vec![0..10, 2..3, 4..12], 20), vec![0..12]);
+    }
+
+    #[test]
+    fn merge_clamps_to_len() {
+        // Ranges pushed before a `truncate`
+        assert_eq!(merge_ranges(vec![0..2, 3..8, 6..7], 5), vec![0..2, 3..5]);
+        assert!(merge_ranges(vec![0..2], 0).is_empty());
+    }
+
+    #[test]
+    fn merge_empty() {
+        assert!(merge_ranges(Vec::new(), 5).is_empty());
+        assert!(merge_ranges(vec![2..2], 5).is_empty());
+    }
+
+    #[test]
+    fn split_on_grow() {
+        // Below the kept elements, written into the old buffer before the copy
+        assert_eq!(split_range(0..3, 4), (0..3, 4..4));
+        // Across, split between both buffers
+        assert_eq!(split_range(2..6, 4), (2..4, 4..6));
+        // Above, written into the new buffer only
+        assert_eq!(split_range(5..7, 4), (4..4, 5..7));
+        // Nothing kept
+        assert_eq!(split_range(0..3, 0), (0..0, 0..3));
+    }
+}